@@ -0,0 +1,119 @@
+//! Binary device Object Store (BOS) descriptor.
+//!
+//! The BOS descriptor lists the device capabilities, such as USB 2.0 extension,
+//! SuperSpeed and platform capabilities. Platform capabilities are identified by a UUID
+//! and are used, for example, to announce WebUSB and Microsoft OS 2.0 descriptor support.
+//! Use [`OpenUsbDevice::bos_descriptor`](crate::OpenUsbDevice::bos_descriptor) to read it from a device.
+
+use crate::{
+    descriptor::{self, Descriptors},
+    Error, ErrorKind, Result,
+};
+
+/// Descriptor type of a device capability descriptor.
+const DEVICE_CAPABILITY: u8 = 0x10;
+
+/// UUID {d8dd60df-4589-4cc7-9cd2-659d9e648a9f} of the Microsoft OS 2.0 platform capability
+/// in the byte order used by USB descriptors.
+pub const MS_OS_20_UUID: [u8; 16] =
+    [0xdf, 0x60, 0xdd, 0xd8, 0x89, 0x45, 0xc7, 0x4c, 0x9c, 0xd2, 0x65, 0x9d, 0x9e, 0x64, 0x8a, 0x9f];
+
+/// A parsed BOS descriptor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BosDescriptor {
+    /// Device capabilities in the order they appear in the descriptor.
+    pub capabilities: Vec<BosCapability>,
+}
+
+impl BosDescriptor {
+    /// Parses a raw BOS descriptor including its device capability descriptors.
+    pub fn parse(raw: &[u8]) -> Result<Self> {
+        let mut descs = Descriptors::new(raw);
+        match descs.next() {
+            Some(header) if header[1] == descriptor::BOS && header.len() >= 5 => (),
+            _ => return Err(Error::new(ErrorKind::InvalidData, "invalid BOS descriptor")),
+        }
+
+        let capabilities = descs
+            .filter(|desc| desc[1] == DEVICE_CAPABILITY && desc.len() >= 3)
+            .map(|desc| BosCapability { capability_type: desc[2], data: desc[3..].to_vec() })
+            .collect();
+
+        Ok(Self { capabilities })
+    }
+
+    /// Platform capability with the specified UUID.
+    ///
+    /// The UUID is specified in the byte order used by USB descriptors.
+    pub fn platform_capability(&self, uuid: &[u8; 16]) -> Option<&BosCapability> {
+        self.capabilities.iter().find(|cap| cap.platform_uuid() == Some(uuid))
+    }
+
+    /// Information about the Microsoft OS 2.0 descriptor set announced by the device.
+    ///
+    /// If the platform capability lists descriptor sets for multiple Windows versions,
+    /// the first one is returned.
+    pub fn ms_os_20_descriptor_set_info(&self) -> Option<MsOs20DescriptorSetInfo> {
+        let data = self.platform_capability(&MS_OS_20_UUID)?.platform_data()?;
+        let &[v0, v1, v2, v3, l0, l1, vendor_code, alt_enum_code, ..] = data else { return None };
+        Some(MsOs20DescriptorSetInfo {
+            windows_version: u32::from_le_bytes([v0, v1, v2, v3]),
+            total_length: u16::from_le_bytes([l0, l1]),
+            vendor_code,
+            alt_enum_code,
+        })
+    }
+}
+
+/// Descriptor set information of a Microsoft OS 2.0 platform capability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MsOs20DescriptorSetInfo {
+    /// Minimum Windows version the descriptor set applies to (`dwWindowsVersion`).
+    pub windows_version: u32,
+    /// Total length of the descriptor set in bytes (`wMSOSDescriptorSetTotalLength`).
+    pub total_length: u16,
+    /// Vendor request code for retrieving the descriptor set (`bMS_VendorCode`).
+    pub vendor_code: u8,
+    /// Alternate enumeration code, which is non-zero if the device supports
+    /// alternate enumeration (`bAltEnumCode`).
+    pub alt_enum_code: u8,
+}
+
+/// A device capability contained in a BOS descriptor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BosCapability {
+    /// Capability type (`bDevCapabilityType`).
+    pub capability_type: u8,
+    /// Capability-specific data following the capability type field.
+    pub data: Vec<u8>,
+}
+
+impl BosCapability {
+    /// Capability type of the USB 2.0 extension capability.
+    pub const USB_20_EXTENSION: u8 = 0x02;
+    /// Capability type of the SuperSpeed USB capability.
+    pub const SUPERSPEED_USB: u8 = 0x03;
+    /// Capability type of the container ID capability.
+    pub const CONTAINER_ID: u8 = 0x04;
+    /// Capability type of platform capabilities.
+    pub const PLATFORM: u8 = 0x05;
+
+    /// UUID of a platform capability in the byte order used by USB descriptors.
+    pub fn platform_uuid(&self) -> Option<&[u8; 16]> {
+        match self.capability_type {
+            Self::PLATFORM => self.data.get(1..17)?.try_into().ok(),
+            _ => None,
+        }
+    }
+
+    /// Capability-specific data of a platform capability following its UUID.
+    pub fn platform_data(&self) -> Option<&[u8]> {
+        match self.capability_type {
+            Self::PLATFORM => self.data.get(17..),
+            _ => None,
+        }
+    }
+}
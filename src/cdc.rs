@@ -0,0 +1,92 @@
+//! USB Communications Device Class (CDC) support.
+//!
+//! CDC devices, such as CDC-ACM serial adapters, describe the relationship between
+//! their communication and data interfaces using class-specific functional descriptors
+//! embedded in the configuration descriptor.
+//! Use [`OpenUsbDevice::raw_configuration_descriptor`](crate::OpenUsbDevice::raw_configuration_descriptor)
+//! to obtain the configuration descriptor and [`parse_cdc_functional_descriptors`] to decode them.
+
+use crate::{
+    class_codes,
+    descriptor::{self, Descriptors},
+};
+
+/// Descriptor type of a class-specific interface descriptor.
+const CS_INTERFACE: u8 = 0x24;
+
+/// A CDC functional descriptor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CdcFunctionalDescriptor {
+    /// Header functional descriptor.
+    Header {
+        /// CDC specification release number in binary-coded decimal.
+        bcd_cdc: u16,
+    },
+    /// Call management functional descriptor.
+    CallManagement {
+        /// Call management capabilities (`bmCapabilities`).
+        capabilities: u8,
+        /// Interface number of the data interface used for call management.
+        data_interface: u8,
+    },
+    /// Abstract control management functional descriptor.
+    AbstractControlManagement {
+        /// Supported abstract control management requests (`bmCapabilities`).
+        capabilities: u8,
+    },
+    /// Union functional descriptor.
+    Union {
+        /// Interface number of the controlling communication interface.
+        control_interface: u8,
+        /// Interface numbers of the subordinate interfaces, usually the data interface.
+        subordinate_interfaces: Vec<u8>,
+    },
+    /// A functional descriptor of another subtype.
+    Other {
+        /// Descriptor subtype (`bDescriptorSubtype`).
+        subtype: u8,
+        /// Descriptor data following the subtype field.
+        data: Vec<u8>,
+    },
+}
+
+impl CdcFunctionalDescriptor {
+    /// Parses a class-specific interface descriptor, including its length and type fields.
+    fn parse(desc: &[u8]) -> Option<Self> {
+        let subtype = *desc.get(2)?;
+        let data = &desc[3..];
+
+        let parsed = match (subtype, data) {
+            (0x00, &[lo, hi, ..]) => Self::Header { bcd_cdc: u16::from_le_bytes([lo, hi]) },
+            (0x01, &[capabilities, data_interface, ..]) => Self::CallManagement { capabilities, data_interface },
+            (0x02, &[capabilities, ..]) => Self::AbstractControlManagement { capabilities },
+            (0x06, &[control_interface, ref subordinate_interfaces @ ..]) => {
+                Self::Union { control_interface, subordinate_interfaces: subordinate_interfaces.to_vec() }
+            }
+            (0x00..=0x02 | 0x06, _) => return None,
+            (subtype, data) => Self::Other { subtype, data: data.to_vec() },
+        };
+        Some(parsed)
+    }
+}
+
+/// Parses the CDC functional descriptors from a raw configuration descriptor.
+///
+/// Only class-specific interface descriptors belonging to CDC communication interfaces
+/// are returned, in the order they appear in the configuration descriptor.
+/// Malformed descriptors are skipped.
+pub fn parse_cdc_functional_descriptors(raw_config: &[u8]) -> Vec<CdcFunctionalDescriptor> {
+    let mut in_cdc_interface = false;
+    let mut descriptors = Vec::new();
+
+    for desc in Descriptors::new(raw_config) {
+        match desc[1] {
+            descriptor::INTERFACE => in_cdc_interface = desc.get(5) == Some(&class_codes::CDC),
+            CS_INTERFACE if in_cdc_interface => descriptors.extend(CdcFunctionalDescriptor::parse(desc)),
+            _ => (),
+        }
+    }
+
+    descriptors
+}
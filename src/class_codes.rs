@@ -0,0 +1,78 @@
+//! Well-known USB class, subclass and protocol codes.
+//!
+//! These are the values assigned by the USB Implementers Forum for use in the
+//! `bDeviceClass` and `bInterfaceClass` fields and the corresponding subclass and
+//! protocol fields of device and interface descriptors.
+//! They can be used to build filters and to inspect interfaces:
+//!
+//! ```
+//! use webusb_web::{class_codes, UsbDeviceFilter};
+//!
+//! let filter = UsbDeviceFilter::new().with_class_code(class_codes::VENDOR_SPECIFIC);
+//! ```
+
+/// Class is specified by each interface of the device.
+pub const PER_INTERFACE: u8 = 0x00;
+/// Audio class.
+pub const AUDIO: u8 = 0x01;
+/// Communications Device Class (CDC) control.
+pub const CDC: u8 = 0x02;
+/// Human Interface Device (HID) class.
+pub const HID: u8 = 0x03;
+/// Physical interface device class.
+pub const PHYSICAL: u8 = 0x05;
+/// Still imaging class.
+pub const IMAGE: u8 = 0x06;
+/// Printer class.
+pub const PRINTER: u8 = 0x07;
+/// Mass storage class.
+pub const MASS_STORAGE: u8 = 0x08;
+/// Hub class.
+pub const HUB: u8 = 0x09;
+/// Communications Device Class (CDC) data.
+pub const CDC_DATA: u8 = 0x0a;
+/// Smart card class.
+pub const SMART_CARD: u8 = 0x0b;
+/// Video class.
+pub const VIDEO: u8 = 0x0e;
+/// Audio/video devices class.
+pub const AUDIO_VIDEO: u8 = 0x10;
+/// Wireless controller class.
+pub const WIRELESS_CONTROLLER: u8 = 0xe0;
+/// Miscellaneous class, used by composite devices with interface associations.
+pub const MISCELLANEOUS: u8 = 0xef;
+/// Application-specific class, for example device firmware upgrade (DFU).
+pub const APPLICATION_SPECIFIC: u8 = 0xfe;
+/// Vendor-specific class.
+pub const VENDOR_SPECIFIC: u8 = 0xff;
+
+/// Audio class subclass: audio control interface.
+pub const AUDIO_SUBCLASS_CONTROL: u8 = 0x01;
+/// Audio class subclass: audio streaming interface.
+pub const AUDIO_SUBCLASS_STREAMING: u8 = 0x02;
+/// Audio class subclass: MIDI streaming interface.
+pub const AUDIO_SUBCLASS_MIDI_STREAMING: u8 = 0x03;
+
+/// CDC subclass: abstract control model (ACM), used by serial adapters.
+pub const CDC_SUBCLASS_ACM: u8 = 0x02;
+/// CDC subclass: Ethernet control model (ECM).
+pub const CDC_SUBCLASS_ECM: u8 = 0x06;
+/// CDC subclass: network control model (NCM).
+pub const CDC_SUBCLASS_NCM: u8 = 0x0d;
+
+/// HID subclass: boot interface.
+pub const HID_SUBCLASS_BOOT: u8 = 0x01;
+/// HID boot interface protocol: keyboard.
+pub const HID_PROTOCOL_KEYBOARD: u8 = 0x01;
+/// HID boot interface protocol: mouse.
+pub const HID_PROTOCOL_MOUSE: u8 = 0x02;
+
+/// Mass storage subclass: SCSI transparent command set.
+pub const MASS_STORAGE_SUBCLASS_SCSI: u8 = 0x06;
+/// Mass storage protocol: bulk-only transport.
+pub const MASS_STORAGE_PROTOCOL_BULK_ONLY: u8 = 0x50;
+/// Mass storage protocol: USB attached SCSI (UAS).
+pub const MASS_STORAGE_PROTOCOL_UAS: u8 = 0x62;
+
+/// Application-specific subclass: device firmware upgrade (DFU).
+pub const APPLICATION_SPECIFIC_SUBCLASS_DFU: u8 = 0x01;
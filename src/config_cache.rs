@@ -0,0 +1,47 @@
+//! Cache of parsed configurations shared by all wrappers of a device.
+//!
+//! Wrappers of the same JavaScript device object are obtained independently, for example
+//! from [`Usb::devices`](crate::Usb::devices) and from connection events. Keying the cache
+//! by the device object ensures that all of them observe invalidation.
+//! Devices are referenced weakly, so that the cache does not keep them alive.
+
+use std::{cell::RefCell, rc::Rc};
+
+use js_sys::WeakRef;
+
+use crate::UsbConfiguration;
+
+/// Parsed configurations of a USB device.
+#[derive(Default)]
+pub(crate) struct ConfigurationCache {
+    /// Active configuration.
+    pub active: Option<Option<UsbConfiguration>>,
+    /// All configurations.
+    pub all: Option<Vec<UsbConfiguration>>,
+}
+
+/// Shared cache of a device.
+type SharedCache = Rc<RefCell<ConfigurationCache>>;
+
+thread_local! {
+    static CACHES: RefCell<Vec<(WeakRef<web_sys::UsbDevice>, SharedCache)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Cache of the device, created if necessary.
+pub(crate) fn get(device: &web_sys::UsbDevice) -> SharedCache {
+    CACHES.with_borrow_mut(|caches| {
+        caches.retain(|(dev, _)| dev.deref().is_some());
+        if let Some((_, cache)) = caches.iter().find(|(dev, _)| dev.deref().as_ref() == Some(device)) {
+            return cache.clone();
+        }
+
+        let cache = SharedCache::default();
+        caches.push((WeakRef::new(device), cache.clone()));
+        cache
+    })
+}
+
+/// Invalidates the cache of the device.
+pub(crate) fn invalidate(device: &web_sys::UsbDevice) {
+    CACHES.with_borrow_mut(|caches| caches.retain(|(dev, _)| dev.deref().is_some_and(|dev| &dev != device)));
+}
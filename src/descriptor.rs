@@ -0,0 +1,173 @@
+//! Raw USB descriptor parsing.
+
+use crate::{
+    Error, ErrorKind, Result, UsbAlternateInterface, UsbConfiguration, UsbDirection, UsbEndpoint,
+    UsbEndpointType, UsbInterface, UsbInterfaceAssociation,
+};
+
+/// Standard `GET_DESCRIPTOR` request.
+pub(crate) const GET_DESCRIPTOR: u8 = 0x06;
+
+/// Descriptor type of a configuration descriptor.
+pub(crate) const CONFIGURATION: u8 = 0x02;
+/// Descriptor type of a string descriptor.
+pub(crate) const STRING: u8 = 0x03;
+/// Descriptor type of an interface descriptor.
+pub(crate) const INTERFACE: u8 = 0x04;
+/// Descriptor type of an endpoint descriptor.
+pub(crate) const ENDPOINT: u8 = 0x05;
+/// Descriptor type of an interface association descriptor.
+pub(crate) const INTERFACE_ASSOCIATION: u8 = 0x0b;
+/// Descriptor type of a BOS descriptor.
+pub(crate) const BOS: u8 = 0x0f;
+/// Descriptor type of a SuperSpeed endpoint companion descriptor.
+pub(crate) const SS_ENDPOINT_COMPANION: u8 = 0x30;
+
+/// Iterator over the descriptors contained in a raw descriptor blob.
+///
+/// Each item is the complete descriptor including its `bLength` and
+/// `bDescriptorType` fields. Iteration stops at the first malformed descriptor.
+#[derive(Debug, Clone)]
+pub(crate) struct Descriptors<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Descriptors<'a> {
+    /// Iterates over the descriptors in `data`.
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+}
+
+impl<'a> Iterator for Descriptors<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = *self.data.first()? as usize;
+        if len < 2 || len > self.data.len() {
+            self.data = &[];
+            return None;
+        }
+
+        let (desc, rest) = self.data.split_at(len);
+        self.data = rest;
+        Some(desc)
+    }
+}
+
+/// Decodes the UTF-16LE text of a raw string descriptor.
+pub(crate) fn parse_string(raw: &[u8]) -> Option<String> {
+    let desc = Descriptors::new(raw).next()?;
+    if desc[1] != STRING {
+        return None;
+    }
+    let units: Vec<u16> = desc[2..].chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+    String::from_utf16(&units).ok()
+}
+
+/// Parses a raw configuration descriptor including its interface and endpoint descriptors.
+pub(crate) fn parse_configuration(raw: &[u8]) -> Result<UsbConfiguration> {
+    let invalid = || Error::new(ErrorKind::InvalidData, "invalid configuration descriptor");
+
+    let mut descs = Descriptors::new(raw);
+    let cfg = match descs.next() {
+        Some(desc) if desc[1] == CONFIGURATION && desc.len() >= 9 => desc,
+        _ => return Err(invalid()),
+    };
+    let mut config = UsbConfiguration {
+        configuration_value: cfg[5],
+        configuration_name: None,
+        interfaces: Vec::new(),
+        self_powered: Some(cfg[7] & 0x40 != 0),
+        remote_wakeup: Some(cfg[7] & 0x20 != 0),
+        max_power_ma: None,
+        interface_associations: Vec::new(),
+    };
+    let mut superspeed = false;
+    // Indices of the interface and alternate setting the following endpoints belong to.
+    let mut current = None;
+
+    for desc in descs {
+        match desc[1] {
+            INTERFACE if desc.len() >= 9 => {
+                let alt = UsbAlternateInterface {
+                    alternate_setting: desc[3],
+                    interface_class: desc[5],
+                    interface_subclass: desc[6],
+                    interface_protocol: desc[7],
+                    interface_name: None,
+                    endpoints: Vec::new(),
+                };
+                match config.interfaces.iter().position(|iface| iface.interface_number == desc[2]) {
+                    Some(idx) => {
+                        let iface = &mut config.interfaces[idx];
+                        iface.alternates.push(alt);
+                        current = Some((idx, iface.alternates.len() - 1));
+                    }
+                    None => {
+                        config.interfaces.push(UsbInterface {
+                            interface_number: desc[2],
+                            alternate: alt.clone(),
+                            alternates: vec![alt],
+                            claimed: false,
+                        });
+                        current = Some((config.interfaces.len() - 1, 0));
+                    }
+                }
+            }
+            ENDPOINT if desc.len() >= 7 => {
+                let Some((iface, alt)) = current else {
+                    return Err(invalid());
+                };
+                let alt = &mut config.interfaces[iface].alternates[alt];
+                let endpoint_type = match desc[3] & 0x03 {
+                    0x01 => UsbEndpointType::Isochronous,
+                    0x02 => UsbEndpointType::Bulk,
+                    0x03 => UsbEndpointType::Interrupt,
+                    _ => continue,
+                };
+                alt.endpoints.push(UsbEndpoint {
+                    endpoint_number: desc[2] & 0x0f,
+                    direction: if desc[2] & 0x80 != 0 { UsbDirection::In } else { UsbDirection::Out },
+                    endpoint_type,
+                    packet_size: u32::from(u16::from_le_bytes([desc[4], desc[5]]) & 0x7ff),
+                    max_burst: None,
+                    bytes_per_interval: None,
+                    interval: Some(desc[6]),
+                    raw_attributes: Some(desc[3]),
+                });
+            }
+            INTERFACE_ASSOCIATION if desc.len() >= 8 => {
+                config.interface_associations.push(UsbInterfaceAssociation {
+                    first_interface: desc[2],
+                    interface_count: desc[3],
+                    function_class: desc[4],
+                    function_subclass: desc[5],
+                    function_protocol: desc[6],
+                });
+            }
+            SS_ENDPOINT_COMPANION if desc.len() >= 6 => {
+                let Some(ep) = current
+                    .and_then(|(iface, alt)| config.interfaces[iface].alternates[alt].endpoints.last_mut())
+                else {
+                    return Err(invalid());
+                };
+                superspeed = true;
+                ep.max_burst = Some(desc[2]);
+                ep.bytes_per_interval = Some(u16::from_le_bytes([desc[4], desc[5]]));
+            }
+            _ => (),
+        }
+    }
+
+    let power_unit = if superspeed { 8 } else { 2 };
+    config.max_power_ma = Some(u16::from(cfg[8]) * power_unit);
+
+    for iface in &mut config.interfaces {
+        if let Some(alt) = iface.alternates.iter().find(|alt| alt.alternate_setting == 0) {
+            iface.alternate = alt.clone();
+        }
+    }
+
+    Ok(config)
+}
@@ -0,0 +1,155 @@
+//! Framing of vendor-specific bulk protocols.
+//!
+//! Many vendor protocols transmit messages as frames consisting of a header, which
+//! optionally starts with a magic byte sequence and contains the length of the payload,
+//! followed by the payload. A frame may be split across multiple transfers and multiple
+//! frames may be received in one transfer.
+//! Use [`OpenUsbDevice::read_framed`](crate::OpenUsbDevice::read_framed) with a [`FrameConfig`]
+//! describing the header to receive complete frames.
+
+use crate::{Error, ErrorKind, Result};
+
+/// Describes the frame format of a protocol.
+///
+/// A frame consists of a header of [`header_len`](Self::header_len) bytes followed by
+/// a payload whose length is stored in the header. All offsets are relative to the
+/// start of the frame, i.e. the magic prefix is part of the header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FrameConfig {
+    /// Byte sequence each frame starts with.
+    ///
+    /// If non-empty, the received data is scanned for it to find the start of a frame,
+    /// which allows resynchronization after corrupted data.
+    pub magic: Vec<u8>,
+    /// Length of the header in bytes, including the magic prefix.
+    pub header_len: usize,
+    /// Offset of the payload length field within the header.
+    pub length_offset: usize,
+    /// Size of the payload length field in bytes, either 1, 2 or 4.
+    pub length_size: usize,
+    /// Whether the payload length field is big endian instead of little endian.
+    pub big_endian: bool,
+    /// Maximum size of a frame including its header.
+    ///
+    /// A header specifying a larger frame is treated as corrupted.
+    pub max_frame_size: usize,
+}
+
+impl FrameConfig {
+    /// Frame format with a header of `header_len` bytes containing the little endian payload
+    /// length of `length_size` bytes at `length_offset`.
+    ///
+    /// No magic prefix is used and the frame size is limited to 64 kiB.
+    pub const fn new(header_len: usize, length_offset: usize, length_size: usize) -> Self {
+        Self {
+            magic: Vec::new(),
+            header_len,
+            length_offset,
+            length_size,
+            big_endian: false,
+            max_frame_size: 65536,
+        }
+    }
+
+    /// Sets the byte sequence each frame starts with.
+    pub fn with_magic(mut self, magic: impl Into<Vec<u8>>) -> Self {
+        self.magic = magic.into();
+        self
+    }
+
+    /// Sets whether the payload length field is big endian.
+    pub const fn with_big_endian(mut self, big_endian: bool) -> Self {
+        self.big_endian = big_endian;
+        self
+    }
+
+    /// Sets the maximum size of a frame including its header.
+    pub const fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Checks that the configuration describes a valid frame format.
+    fn validate(&self) -> Result<()> {
+        let invalid = |msg| Err(Error::new(ErrorKind::InvalidAccess, msg));
+        if !matches!(self.length_size, 1 | 2 | 4) {
+            return invalid("frame length field must be 1, 2 or 4 bytes long");
+        }
+        if self.length_offset + self.length_size > self.header_len || self.magic.len() > self.header_len {
+            return invalid("frame header is too short");
+        }
+        Ok(())
+    }
+}
+
+/// Assembles frames from received data.
+#[derive(Debug)]
+pub(crate) struct FrameParser {
+    config: FrameConfig,
+    buf: Vec<u8>,
+    /// Offset of the first unprocessed byte in `buf`.
+    start: usize,
+}
+
+impl FrameParser {
+    /// Creates a parser for the specified frame format.
+    pub(crate) fn new(config: FrameConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self { config, buf: Vec::new(), start: 0 })
+    }
+
+    /// Appends received data.
+    pub(crate) fn push(&mut self, data: &[u8]) {
+        self.buf.drain(..self.start);
+        self.start = 0;
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Removes and returns the next complete frame, if available.
+    ///
+    /// Data preceding the magic prefix and frames with a corrupted header are discarded.
+    pub(crate) fn next_frame(&mut self) -> Option<Vec<u8>> {
+        let FrameConfig { magic, header_len, length_offset, length_size, big_endian, max_frame_size } =
+            &self.config;
+
+        loop {
+            let data = &self.buf[self.start..];
+            if !magic.is_empty() {
+                match data.windows(magic.len()).position(|w| w == magic) {
+                    Some(pos) => self.start += pos,
+                    None => {
+                        self.start += data.len() - data.len().min(magic.len() - 1);
+                        return None;
+                    }
+                }
+            }
+            let data = &self.buf[self.start..];
+            if data.len() < *header_len {
+                return None;
+            }
+
+            let field = &data[*length_offset..length_offset + length_size];
+            let mut bytes = [0; 4];
+            if *big_endian {
+                bytes[4 - length_size..].copy_from_slice(field);
+            } else {
+                bytes[..*length_size].copy_from_slice(field);
+            }
+            let payload_len = if *big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) };
+
+            let frame_len = header_len.saturating_add(payload_len as usize);
+            if frame_len > *max_frame_size {
+                // Corrupted header, resynchronize after its first byte.
+                self.start += 1;
+                continue;
+            }
+            if data.len() < frame_len {
+                return None;
+            }
+
+            self.start += frame_len;
+            return Some(data[..frame_len].to_vec());
+        }
+    }
+}
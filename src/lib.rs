@@ -16,6 +16,12 @@
 //! Therefore you must add `--cfg=web_sys_unstable_apis` to the Rust
 //! compiler flags (`RUSTFLAGS`).
 //!
+//! ### Features
+//! * `bytemuck` — enables [`OpenUsbDevice::control_read_pod`] for reading plain-old-data
+//!   structures using control transfers.
+//! * `serde` — implements `Serialize` and `Deserialize` for [`UsbDeviceIdentity`] and
+//!   [`UsbDeviceFilter`], allowing them to be persisted, for example in local storage.
+//!
 //! ### Usage
 //! Call [`Usb::new()`] to obtain an interface to the WebUSB API.
 //! You must call [`Usb::request_device()`] to ask the user for permission before
@@ -24,22 +30,45 @@
 
 #![warn(missing_docs)]
 
+pub mod bos;
+pub mod cdc;
+pub mod class_codes;
+mod config_cache;
+mod descriptor;
+pub mod frame;
+mod session;
+mod time;
+
+pub use time::Deadline;
+
 use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, BTreeSet, VecDeque},
     fmt,
+    future::{pending, Future},
     hash::{Hash, Hasher},
+    iter,
     marker::PhantomData,
-    pin::Pin,
+    mem,
+    pin::{pin, Pin},
+    rc::{self, Rc},
     task::{ready, Context, Poll},
+    time::Duration,
 };
 
 use futures_core::Stream;
-use futures_util::StreamExt;
-use js_sys::{Reflect, Uint8Array};
-use tokio::sync::broadcast;
+use futures_util::{
+    future::{self, Either},
+    stream, StreamExt,
+};
+use js_sys::{Promise, Reflect, Uint8Array};
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
 use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
 use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
 use wasm_bindgen_futures::{spawn_local, JsFuture};
 
+use session::Session;
+
 /// WebUSB error.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Error {
@@ -89,26 +118,82 @@ pub enum ErrorKind {
     Transfer,
     /// Invalid access.
     InvalidAccess,
+    /// The operation did not complete within the specified time.
+    TimedOut,
+    /// The operation was cancelled.
+    Cancelled,
+    /// The received data is malformed, for example it has an unexpected length.
+    InvalidData,
+    /// The user closed the device chooser without selecting a device.
+    NoDeviceSelected,
+    /// A browser limit, such as the number of simultaneously open devices, was exceeded.
+    ///
+    /// Closing other devices may allow the operation to succeed.
+    QuotaExceeded,
     /// Other error.
     Other,
 }
 
+impl ErrorKind {
+    /// Maps the name of a DOM exception raised by the browser to an error kind.
+    ///
+    /// This is the mapping used when converting a JavaScript exception into an [`Error`].
+    /// Unknown names map to [`ErrorKind::Other`].
+    pub fn from_dom_name(name: &str) -> Self {
+        match name {
+            "NotFoundError" => Self::Disconnected,
+            "SecurityError" => Self::Security,
+            "InvalidAccessError" => Self::InvalidAccess,
+            "NetworkError" => Self::Transfer,
+            "NotSupportedError" => Self::Unsupported,
+            "QuotaExceededError" => Self::QuotaExceeded,
+            _ => Self::Other,
+        }
+    }
+}
+
 impl Error {
     fn new(kind: ErrorKind, msg: impl AsRef<str>) -> Self {
         Self { kind, msg: msg.as_ref().to_string() }
     }
+
+    /// Whether an error message describes an exceeded browser limit.
+    fn is_quota_message(msg: &str) -> bool {
+        let msg = msg.to_lowercase();
+        msg.contains("quota") || msg.contains("too many")
+    }
+
+    /// Error for a value of unexpected shape returned by the browser.
+    ///
+    /// The value is logged to the console to aid debugging of browser divergences.
+    fn unexpected(what: &str, value: &JsValue) -> Self {
+        let msg = format!("browser returned unexpected {what}");
+        web_sys::console::error_2(&JsValue::from_str(&format!("webusb-web: {msg}:")), value);
+        Self::new(ErrorKind::Other, msg)
+    }
+}
+
+/// Casts a value returned by the browser to the expected type.
+///
+/// Instead of panicking, an error of kind [`ErrorKind::Other`] is returned if the
+/// value has an unexpected type.
+fn cast<T: JsCast>(value: impl Into<JsValue>, what: &str) -> Result<T> {
+    value.into().dyn_into().map_err(|value| Error::unexpected(what, &value))
+}
+
+/// Ensures that a value expected to be provided by the browser is present.
+fn present<T>(value: Option<T>, what: &str) -> Result<T> {
+    value.ok_or_else(|| Error::unexpected(what, &JsValue::UNDEFINED))
 }
 
 impl From<JsValue> for Error {
     fn from(value: JsValue) -> Self {
         if let Some(js_error) = value.dyn_ref::<js_sys::Error>() {
-            let msg = js_error.message().as_string().unwrap();
-            let kind = match js_error.name().as_string().unwrap().as_str() {
-                "NotFoundError" => ErrorKind::Disconnected,
-                "SecurityError" => ErrorKind::Security,
-                "InvalidAccessError" => ErrorKind::InvalidAccess,
-                "NetworkError" => ErrorKind::Transfer,
-                _ => ErrorKind::Other,
+            let msg = js_error.message().as_string().unwrap_or_default();
+            let kind = match ErrorKind::from_dom_name(&js_error.name().as_string().unwrap_or_default()) {
+                // Some browsers report exceeded limits using a generic exception.
+                ErrorKind::Other if Self::is_quota_message(&msg) => ErrorKind::QuotaExceeded,
+                kind => kind,
             };
             return Error::new(kind, msg);
         }
@@ -129,6 +214,11 @@ impl From<Error> for std::io::Error {
             ErrorKind::Babble => std::io::ErrorKind::UnexpectedEof,
             ErrorKind::Transfer => std::io::ErrorKind::ConnectionReset,
             ErrorKind::InvalidAccess => std::io::ErrorKind::InvalidInput,
+            ErrorKind::TimedOut => std::io::ErrorKind::TimedOut,
+            ErrorKind::Cancelled => std::io::ErrorKind::Interrupted,
+            ErrorKind::InvalidData => std::io::ErrorKind::InvalidData,
+            ErrorKind::NoDeviceSelected => std::io::ErrorKind::NotFound,
+            ErrorKind::QuotaExceeded => std::io::ErrorKind::QuotaExceeded,
             ErrorKind::Other => std::io::ErrorKind::Other,
         };
         std::io::Error::new(kind, err)
@@ -139,7 +229,7 @@ impl From<Error> for std::io::Error {
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// A configuration belonging to a USB device.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 pub struct UsbConfiguration {
     /// The configuration value of this configuration.
@@ -155,6 +245,28 @@ pub struct UsbConfiguration {
     pub configuration_name: Option<String>,
     /// The interfaces available under this configuration.
     pub interfaces: Vec<UsbInterface>,
+    /// Whether the device is self-powered in this configuration.
+    ///
+    /// This is bit 6 of the `bmAttributes` field of the configuration descriptor.
+    /// It is only available when the configuration was obtained from the raw configuration descriptor.
+    pub self_powered: Option<bool>,
+    /// Whether the device supports remote wakeup in this configuration.
+    ///
+    /// This is bit 5 of the `bmAttributes` field of the configuration descriptor.
+    /// It is only available when the configuration was obtained from the raw configuration descriptor.
+    pub remote_wakeup: Option<bool>,
+    /// Maximum power consumption from the bus in this configuration in milliamperes.
+    ///
+    /// This is derived from the `bMaxPower` field of the configuration descriptor, which is
+    /// specified in units of 8 mA for SuperSpeed devices and 2 mA otherwise. A device is considered
+    /// SuperSpeed if its configuration contains endpoint companion descriptors.
+    /// It is only available when the configuration was obtained from the raw configuration descriptor.
+    pub max_power_ma: Option<u16>,
+    /// Interface association descriptors grouping interfaces into functions.
+    ///
+    /// These are only available when the configuration was obtained from the raw configuration
+    /// descriptor and are empty otherwise.
+    pub interface_associations: Vec<UsbInterfaceAssociation>,
 }
 
 impl From<&web_sys::UsbConfiguration> for UsbConfiguration {
@@ -170,12 +282,188 @@ impl From<&web_sys::UsbConfiguration> for UsbConfiguration {
             configuration_value: conf.configuration_value(),
             configuration_name: conf.configuration_name(),
             interfaces,
+            self_powered: None,
+            remote_wakeup: None,
+            max_power_ma: None,
+            interface_associations: Vec::new(),
+        }
+    }
+}
+
+impl UsbConfiguration {
+    /// Parses a raw configuration descriptor, as returned by
+    /// [`OpenUsbDevice::raw_configuration_descriptor`].
+    ///
+    /// Names are not available, since these are provided by separate string descriptors,
+    /// and all interfaces are reported as not claimed with alternate setting 0 selected.
+    pub fn from_raw_descriptor(raw: &[u8]) -> Result<Self> {
+        descriptor::parse_configuration(raw)
+    }
+
+    /// Copies the fields only available from the raw configuration descriptor from `raw`.
+    fn merge_raw(&mut self, raw: &UsbConfiguration) {
+        self.self_powered = raw.self_powered;
+        self.remote_wakeup = raw.remote_wakeup;
+        self.max_power_ma = raw.max_power_ma;
+        self.interface_associations = raw.interface_associations.clone();
+
+        for iface in &mut self.interfaces {
+            let Some(raw_iface) = raw.interfaces.iter().find(|r| r.interface_number == iface.interface_number)
+            else {
+                continue;
+            };
+            for alt in iter::once(&mut iface.alternate).chain(&mut iface.alternates) {
+                let Some(raw_alt) =
+                    raw_iface.alternates.iter().find(|r| r.alternate_setting == alt.alternate_setting)
+                else {
+                    continue;
+                };
+                for ep in &mut alt.endpoints {
+                    let Some(raw_ep) = raw_alt
+                        .endpoints
+                        .iter()
+                        .find(|r| r.endpoint_number == ep.endpoint_number && r.direction == ep.direction)
+                    else {
+                        continue;
+                    };
+                    ep.max_burst = raw_ep.max_burst;
+                    ep.bytes_per_interval = raw_ep.bytes_per_interval;
+                    ep.interval = raw_ep.interval;
+                    ep.raw_attributes = raw_ep.raw_attributes;
+                }
+            }
+        }
+    }
+
+    /// Iterates over all endpoints of all alternate settings of all interfaces of this configuration.
+    ///
+    /// Each endpoint is yielded together with the interface and alternate setting it belongs to.
+    pub fn iter_endpoints(&self) -> impl Iterator<Item = (&UsbInterface, &UsbAlternateInterface, &UsbEndpoint)> {
+        self.interfaces.iter().flat_map(|iface| {
+            iface.alternates.iter().flat_map(move |alt| alt.endpoints.iter().map(move |ep| (iface, alt, ep)))
+        })
+    }
+
+    /// The interface owning the specified endpoint in any of its alternate settings.
+    pub fn interface_for_endpoint(&self, endpoint_number: u8, direction: UsbDirection) -> Option<&UsbInterface> {
+        self.iter_endpoints()
+            .find(|(_, _, ep)| ep.endpoint_number == endpoint_number && ep.direction == direction)
+            .map(|(iface, _, _)| iface)
+    }
+
+    /// The functions provided by this configuration.
+    ///
+    /// For composite devices, interfaces are grouped into functions according to the
+    /// [interface association descriptors](Self::interface_associations). Each interface
+    /// not covered by an association forms a function on its own, described by the class codes
+    /// of its alternate setting 0. Functions are ordered by their first interface number.
+    pub fn functions(&self) -> Vec<DeviceFunction> {
+        let mut functions: Vec<_> = self
+            .interface_associations
+            .iter()
+            .map(|iad| DeviceFunction {
+                interfaces: (iad.first_interface..).take(iad.interface_count.into()).collect(),
+                class: iad.function_class,
+                subclass: iad.function_subclass,
+                protocol: iad.function_protocol,
+            })
+            .collect();
+
+        for iface in &self.interfaces {
+            if functions.iter().any(|f| f.interfaces.contains(&iface.interface_number)) {
+                continue;
+            }
+            let alt = iface.alternates.iter().find(|alt| alt.alternate_setting == 0).unwrap_or(&iface.alternate);
+            functions.push(DeviceFunction {
+                interfaces: vec![iface.interface_number],
+                class: alt.interface_class,
+                subclass: alt.interface_subclass,
+                protocol: alt.interface_protocol,
+            });
         }
+
+        functions.sort_by_key(|f| f.interfaces.first().copied());
+        functions
+    }
+
+    /// Endpoint numbers used by more than one claimed interface.
+    ///
+    /// Composite devices may reuse an endpoint number in different interfaces with different
+    /// directions. Code identifying endpoints by number alone then becomes ambiguous and should
+    /// use the direction as well, for example via [`OpenUsbDevice::transfer_in_addr`].
+    /// The active alternate settings of the claimed interfaces are considered.
+    /// The numbers are returned in ascending order.
+    pub fn conflicting_endpoints(&self) -> Vec<u8> {
+        let mut owners: Vec<(u8, u8)> = self
+            .interfaces
+            .iter()
+            .filter(|iface| iface.claimed)
+            .flat_map(|iface| {
+                iface.alternate.endpoints.iter().map(|ep| (ep.endpoint_number, iface.interface_number))
+            })
+            .collect();
+        owners.sort_unstable();
+        owners.dedup();
+
+        let mut conflicts: Vec<u8> = owners.windows(2).filter(|w| w[0].0 == w[1].0).map(|w| w[0].0).collect();
+        conflicts.dedup();
+        conflicts
+    }
+
+    /// Whether any endpoint number is used by more than one claimed interface.
+    ///
+    /// See [`conflicting_endpoints`](Self::conflicting_endpoints) for details.
+    pub fn has_endpoint_conflicts(&self) -> bool {
+        !self.conflicting_endpoints().is_empty()
     }
+
+    /// The names of all interfaces, taken from their active alternate settings.
+    ///
+    /// Each name is returned together with the number of its interface.
+    pub fn interface_names(&self) -> Vec<(u8, Option<String>)> {
+        self.interfaces
+            .iter()
+            .map(|iface| (iface.interface_number, iface.alternate.interface_name.clone()))
+            .collect()
+    }
+}
+
+/// An interface association grouping consecutive interfaces into a function.
+///
+/// This corresponds to an interface association descriptor (IAD).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct UsbInterfaceAssociation {
+    /// Number of the first interface of the function (`bFirstInterface`).
+    pub first_interface: u8,
+    /// Number of consecutive interfaces belonging to the function (`bInterfaceCount`).
+    pub interface_count: u8,
+    /// Class code of the function (`bFunctionClass`).
+    pub function_class: u8,
+    /// Subclass code of the function (`bFunctionSubClass`).
+    pub function_subclass: u8,
+    /// Protocol code of the function (`bFunctionProtocol`).
+    pub function_protocol: u8,
+}
+
+/// A function of a USB device, consisting of one or more interfaces.
+///
+/// Returned by [`UsbConfiguration::functions`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DeviceFunction {
+    /// Numbers of the interfaces belonging to the function.
+    pub interfaces: Vec<u8>,
+    /// Class code of the function.
+    pub class: u8,
+    /// Subclass code of the function.
+    pub subclass: u8,
+    /// Protocol code of the function.
+    pub protocol: u8,
 }
 
 /// A USB interface grouping one or more alternate settings.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 pub struct UsbInterface {
     /// The interface number.
@@ -216,7 +504,7 @@ impl From<&web_sys::UsbInterface> for UsbInterface {
 }
 
 /// An alternate setting containing detailed interface information.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 pub struct UsbAlternateInterface {
     /// The alternate setting number of this interface.
@@ -249,8 +537,9 @@ impl From<&web_sys::UsbAlternateInterface> for UsbAlternateInterface {
         let ep_list = alt.endpoints();
         let mut endpoints = Vec::new();
         for i in 0..ep_list.length() {
-            if let Some(ep) = ep_list.get(i).dyn_ref::<web_sys::UsbEndpoint>() {
-                endpoints.push(UsbEndpoint::from(ep));
+            // Endpoints of unknown type or direction are logged and skipped.
+            if let Some(Ok(ep)) = ep_list.get(i).dyn_ref::<web_sys::UsbEndpoint>().map(UsbEndpoint::try_from) {
+                endpoints.push(ep);
             }
         }
 
@@ -265,8 +554,25 @@ impl From<&web_sys::UsbAlternateInterface> for UsbAlternateInterface {
     }
 }
 
+impl UsbAlternateInterface {
+    /// Numbers of the first bulk IN and the first bulk OUT endpoint, in this order.
+    ///
+    /// This is the endpoint pair commonly used for exchanging data with vendor-specific
+    /// interfaces, for example using [`OpenUsbDevice::duplex`].
+    /// Returns `None` if the alternate setting does not have both a bulk IN and a bulk OUT endpoint.
+    pub fn duplex_pair(&self) -> Option<(u8, u8)> {
+        let bulk = |direction| {
+            self.endpoints
+                .iter()
+                .find(|ep| ep.endpoint_type == UsbEndpointType::Bulk && ep.direction == direction)
+                .map(|ep| ep.endpoint_number)
+        };
+        Some((bulk(UsbDirection::In)?, bulk(UsbDirection::Out)?))
+    }
+}
+
 /// A USB endpoint provided by the USB device.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 pub struct UsbEndpoint {
     /// The endpoint's "endpoint number" which is a value from 1 to 15 extracted from the
@@ -280,16 +586,54 @@ pub struct UsbEndpoint {
     pub endpoint_type: UsbEndpointType,
     /// The size of the packets that data sent through this endpoint will be divided into.
     pub packet_size: u32,
+    /// The maximum number of packets minus one the endpoint can send or receive as part of a burst.
+    ///
+    /// This is equal to the `bMaxBurst` field of the SuperSpeed endpoint companion descriptor.
+    /// It is only available for SuperSpeed devices when the configuration was obtained
+    /// from the raw configuration descriptor.
+    pub max_burst: Option<u8>,
+    /// The total number of bytes a periodic endpoint transfers every service interval.
+    ///
+    /// This is equal to the `wBytesPerInterval` field of the SuperSpeed endpoint companion descriptor.
+    /// It is only available for SuperSpeed devices when the configuration was obtained
+    /// from the raw configuration descriptor.
+    pub bytes_per_interval: Option<u16>,
+    /// The interval for polling the endpoint for data transfers.
+    ///
+    /// This is equal to the `bInterval` field of the endpoint descriptor and only available
+    /// when the configuration was obtained from the raw configuration descriptor.
+    /// Its unit depends on the device speed and the endpoint type:
+    ///
+    /// * full-speed interrupt endpoints are polled every `interval` frames of 1 ms,
+    /// * full-speed isochronous endpoints every 2<sup>`interval`-1</sup> frames of 1 ms,
+    /// * high-speed and SuperSpeed interrupt and isochronous endpoints every
+    ///   2<sup>`interval`-1</sup> microframes of 125 µs.
+    ///
+    /// For bulk endpoints the value has no meaning for reading.
+    pub interval: Option<u8>,
+    /// The raw attributes of the endpoint.
+    ///
+    /// This is equal to the `bmAttributes` field of the endpoint descriptor and only available
+    /// when the configuration was obtained from the raw configuration descriptor.
+    /// Bits 0..=1 contain the transfer type, from which [`endpoint_type`](Self::endpoint_type)
+    /// is derived, and bits 2..=5 the synchronization and usage type of isochronous endpoints.
+    pub raw_attributes: Option<u8>,
 }
 
-impl From<&web_sys::UsbEndpoint> for UsbEndpoint {
-    fn from(ep: &web_sys::UsbEndpoint) -> Self {
-        Self {
+impl TryFrom<&web_sys::UsbEndpoint> for UsbEndpoint {
+    type Error = Error;
+
+    fn try_from(ep: &web_sys::UsbEndpoint) -> Result<Self> {
+        Ok(Self {
             endpoint_number: ep.endpoint_number(),
-            direction: ep.direction().into(),
-            endpoint_type: ep.type_().into(),
+            direction: ep.direction().try_into()?,
+            endpoint_type: ep.type_().try_into()?,
             packet_size: ep.packet_size(),
-        }
+            max_burst: None,
+            bytes_per_interval: None,
+            interval: None,
+            raw_attributes: None,
+        })
     }
 }
 
@@ -314,19 +658,27 @@ pub enum UsbEndpointType {
     Isochronous,
 }
 
-impl From<web_sys::UsbEndpointType> for UsbEndpointType {
-    fn from(value: web_sys::UsbEndpointType) -> Self {
+impl TryFrom<web_sys::UsbEndpointType> for UsbEndpointType {
+    type Error = Error;
+
+    fn try_from(value: web_sys::UsbEndpointType) -> Result<Self> {
         match value {
-            web_sys::UsbEndpointType::Bulk => Self::Bulk,
-            web_sys::UsbEndpointType::Interrupt => Self::Interrupt,
-            web_sys::UsbEndpointType::Isochronous => Self::Isochronous,
-            other => unreachable!("unsupported UsbEndpointType: {other:?}"),
+            web_sys::UsbEndpointType::Bulk => Ok(Self::Bulk),
+            web_sys::UsbEndpointType::Interrupt => Ok(Self::Interrupt),
+            web_sys::UsbEndpointType::Isochronous => Ok(Self::Isochronous),
+            other => Err(Error::unexpected("endpoint type", &format!("{other:?}").into())),
         }
     }
 }
 
 /// A USB device.
-#[derive(Clone, PartialEq, Eq)]
+///
+/// Parsed configurations are cached and shared between all `UsbDevice` values referring
+/// to the same device, regardless of whether they were obtained from [`Usb::devices`],
+/// a connection event or by other means.
+/// The cache is invalidated when the device is reset or the configuration, an alternate setting
+/// or the claim state of an interface is changed through an [`OpenUsbDevice`].
+#[derive(Clone)]
 pub struct UsbDevice {
     device: web_sys::UsbDevice,
 }
@@ -387,6 +739,33 @@ impl UsbDevice {
         self.device.usb_version_subminor()
     }
 
+    /// USB protocol version supported by the device as binary-coded decimal.
+    ///
+    /// This is the `bcdUSB` field of the device descriptor, for example `0x0210` for USB 2.1.
+    /// It allows comparing versions, e.g. `dev.usb_version_bcd() >= 0x0300` for USB 3.0 or later.
+    pub fn usb_version_bcd(&self) -> u16 {
+        let major = self.usb_version_major();
+        let bcd_major = u16::from(major / 10 % 10) << 4 | u16::from(major % 10);
+        bcd_major << 8
+            | u16::from(self.usb_version_minor() & 0x0f) << 4
+            | u16::from(self.usb_version_subminor() & 0x0f)
+    }
+
+    /// Best-effort estimate of the speed of the device.
+    ///
+    /// WebUSB does not expose the negotiated bus speed, thus this is derived from the
+    /// USB protocol version supported by the device. It is an approximation: a device
+    /// may support a higher speed than the one negotiated with the host, for example
+    /// when connected through a USB 2.0 hub.
+    pub fn speed(&self) -> UsbSpeed {
+        match self.usb_version_major() {
+            0 => UsbSpeed::Unknown,
+            1 => UsbSpeed::Full,
+            2 => UsbSpeed::High,
+            _ => UsbSpeed::Super,
+        }
+    }
+
     /// Optional manufacturer name.
     pub fn manufacturer_name(&self) -> Option<String> {
         self.device.manufacturer_name()
@@ -408,38 +787,461 @@ impl UsbDevice {
     }
 
     /// Active configuration value if any.
+    ///
+    /// The parsed configuration is cached. Parsing requires one call into JavaScript for each
+    /// property of the configuration and of each of its interfaces, alternate settings and
+    /// endpoints, i.e. roughly 50 calls for a configuration with one interface having a single
+    /// alternate setting with two endpoints. A cached call only looks up the device in the cache.
     pub fn configuration(&self) -> Option<UsbConfiguration> {
-        self.device.configuration().map(|cfg| (&cfg).into())
+        config_cache::get(&self.device)
+            .borrow_mut()
+            .active
+            .get_or_insert_with(|| self.device.configuration().map(|cfg| (&cfg).into()))
+            .clone()
     }
 
     /// All available configurations for this device.
+    ///
+    /// The parsed configurations are cached. Parsing requires one call into JavaScript for each
+    /// property of the configuration and of each of its interfaces, alternate settings and
+    /// endpoints, i.e. roughly 50 calls for a configuration with one interface having a single
+    /// alternate setting with two endpoints. A cached call only looks up the device in the cache.
     pub fn configurations(&self) -> Vec<UsbConfiguration> {
-        let cfg_list = self.device.configurations();
-        let mut configurations = Vec::new();
-        for i in 0..cfg_list.length() {
-            if let Some(conf) = cfg_list.get(i).dyn_ref::<web_sys::UsbConfiguration>() {
-                configurations.push(UsbConfiguration::from(conf));
+        config_cache::get(&self.device)
+            .borrow_mut()
+            .all
+            .get_or_insert_with(|| {
+                let cfg_list = self.device.configurations();
+                let mut configurations = Vec::new();
+                for i in 0..cfg_list.length() {
+                    if let Some(conf) = cfg_list.get(i).dyn_ref::<web_sys::UsbConfiguration>() {
+                        configurations.push(UsbConfiguration::from(conf));
+                    }
+                }
+                configurations
+            })
+            .clone()
+    }
+
+    /// Configuration value of the configuration at the specified zero-based index
+    /// of [`configurations`](Self::configurations).
+    ///
+    /// Configuration values are not necessarily contiguous or starting at one, thus the
+    /// index must be mapped to its value before passing it to
+    /// [`OpenUsbDevice::select_configuration`].
+    /// Returns `None` if the index is out of range.
+    pub fn configuration_value_at(&self, index: usize) -> Option<u8> {
+        self.configurations().get(index).map(|cfg| cfg.configuration_value)
+    }
+
+    /// Returns a builder for opening the USB device and preparing it for transfers.
+    ///
+    /// ```no_run
+    /// # async fn example(dev: webusb_web::UsbDevice) -> webusb_web::Result<()> {
+    /// let open = dev.open_builder().configuration(1).claim(0).alternate(0, 1).open().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open_builder(&self) -> OpenUsbDeviceBuilder {
+        OpenUsbDeviceBuilder {
+            device: self.clone(),
+            configuration: None,
+            claim: Vec::new(),
+            alternates: Vec::new(),
+        }
+    }
+
+    /// Converts a JavaScript exception raised by opening the device into an error.
+    fn open_error(value: JsValue) -> Error {
+        let mut err = Error::from(value);
+        let msg = err.msg.to_lowercase();
+        if ["busy", "in use", "already open"].iter().any(|pat| msg.contains(pat)) {
+            err.kind = ErrorKind::AlreadyOpen;
+        }
+        err
+    }
+
+    /// Invalidates the cached configurations.
+    fn invalidate_cache(&self) {
+        config_cache::invalidate(&self.device);
+    }
+
+    /// Iterates over all endpoints of all configurations of this device.
+    ///
+    /// Each endpoint is yielded together with the configuration, interface and alternate setting
+    /// it belongs to. Since these are cloned for each endpoint, prefer
+    /// [`UsbConfiguration::iter_endpoints`] when only one configuration is of interest.
+    pub fn iter_endpoints(
+        &self,
+    ) -> impl Iterator<Item = (UsbConfiguration, UsbInterface, UsbAlternateInterface, UsbEndpoint)> {
+        self.configurations().into_iter().flat_map(|cfg| {
+            cfg.iter_endpoints()
+                .map(|(iface, alt, ep)| (cfg.clone(), iface.clone(), alt.clone(), ep.clone()))
+                .collect::<Vec<_>>()
+        })
+    }
+
+    /// Endpoints of the active alternate settings of all interfaces of the active configuration.
+    ///
+    /// Each endpoint is returned together with the number of the interface it belongs to.
+    /// Returns an empty list if the device is not configured.
+    pub fn active_endpoints(&self) -> Vec<(u8, UsbEndpoint)> {
+        let Some(cfg) = self.configuration() else { return Vec::new() };
+        cfg.interfaces
+            .into_iter()
+            .flat_map(|iface| {
+                let interface_number = iface.interface_number;
+                iface.alternate.endpoints.into_iter().map(move |ep| (interface_number, ep))
+            })
+            .collect()
+    }
+
+    /// Endpoints of the specified transfer type and direction within the active configuration.
+    ///
+    /// Each endpoint is returned as a pair of interface number and endpoint number.
+    /// Only the active alternate settings are considered, see [`active_endpoints`](Self::active_endpoints).
+    pub fn endpoints_of(&self, endpoint_type: UsbEndpointType, direction: UsbDirection) -> Vec<(u8, u8)> {
+        self.active_endpoints()
+            .into_iter()
+            .filter(|(_, ep)| ep.endpoint_type == endpoint_type && ep.direction == direction)
+            .map(|(interface, ep)| (interface, ep.endpoint_number))
+            .collect()
+    }
+
+    /// Returns whether both devices have identical descriptors.
+    ///
+    /// Unlike `==`, which checks whether both refer to the same physical device, this compares
+    /// all descriptor-derived fields of the device, including its identifiers, versions and names,
+    /// as well as the complete tree of configurations, interfaces and endpoints.
+    /// This allows detecting, for example, firmware changes across reconnects.
+    ///
+    /// The comparison is deep and parses all configurations of both devices,
+    /// thus it is considerably more expensive than `==`.
+    pub fn descriptor_eq(&self, other: &UsbDevice) -> bool {
+        self.vendor_id() == other.vendor_id()
+            && self.product_id() == other.product_id()
+            && self.device_class() == other.device_class()
+            && self.device_subclass() == other.device_subclass()
+            && self.device_protocol() == other.device_protocol()
+            && self.device_version_major() == other.device_version_major()
+            && self.device_version_minor() == other.device_version_minor()
+            && self.device_version_subminor() == other.device_version_subminor()
+            && self.usb_version_major() == other.usb_version_major()
+            && self.usb_version_minor() == other.usb_version_minor()
+            && self.usb_version_subminor() == other.usb_version_subminor()
+            && self.manufacturer_name() == other.manufacturer_name()
+            && self.product_name() == other.product_name()
+            && self.serial_number() == other.serial_number()
+            && self.configurations() == other.configurations()
+    }
+
+    /// Textual description of the device and all its configurations, interfaces,
+    /// alternate settings and endpoints, similar to the output of `lsusb -v`.
+    ///
+    /// This is intended for diagnostics, for example to be included in bug reports.
+    /// The format is not stable and should not be parsed.
+    pub fn describe(&self) -> String {
+        use fmt::Write;
+
+        let name = |name: Option<String>| name.map(|name| format!(" {name:?}")).unwrap_or_default();
+        let active = self.configuration().map(|cfg| cfg.configuration_value);
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "Device {:04x}:{:04x}{}{}",
+            self.vendor_id(),
+            self.product_id(),
+            name(self.manufacturer_name()),
+            name(self.product_name())
+        );
+        let _ = writeln!(
+            out,
+            "  USB {}.{}.{}, device version {}.{}.{}, class {:#04x}/{:#04x}/{:#04x}, serial number {}",
+            self.usb_version_major(),
+            self.usb_version_minor(),
+            self.usb_version_subminor(),
+            self.device_version_major(),
+            self.device_version_minor(),
+            self.device_version_subminor(),
+            self.device_class(),
+            self.device_subclass(),
+            self.device_protocol(),
+            self.serial_number().map(|serial| format!("{serial:?}")).unwrap_or_else(|| "none".to_string()),
+        );
+        for cfg in self.configurations() {
+            let state = if active == Some(cfg.configuration_value) { " (active)" } else { "" };
+            let _ = writeln!(
+                out,
+                "  Configuration {}{state}{}",
+                cfg.configuration_value,
+                name(cfg.configuration_name)
+            );
+            for iface in &cfg.interfaces {
+                for alt in &iface.alternates {
+                    let _ = writeln!(
+                        out,
+                        "    Interface {} alternate {}: class {:#04x}/{:#04x}/{:#04x}{}",
+                        iface.interface_number,
+                        alt.alternate_setting,
+                        alt.interface_class,
+                        alt.interface_subclass,
+                        alt.interface_protocol,
+                        name(alt.interface_name.clone())
+                    );
+                    for ep in &alt.endpoints {
+                        let address = match ep.direction {
+                            UsbDirection::In => ep.endpoint_number | 0x80,
+                            UsbDirection::Out => ep.endpoint_number,
+                        };
+                        let _ = writeln!(
+                            out,
+                            "      Endpoint {address:#04x} {:?} {:?}, packet size {}",
+                            ep.direction, ep.endpoint_type, ep.packet_size
+                        );
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Identity of this device, which can be used to find it again after a page reload.
+    pub fn identity(&self) -> UsbDeviceIdentity {
+        UsbDeviceIdentity {
+            vendor_id: self.vendor_id(),
+            product_id: self.product_id(),
+            serial_number: self.serial_number(),
+        }
+    }
+
+    /// Checks that the active configuration of the device matches the expectation of a driver.
+    ///
+    /// The active alternate setting of each expected interface is checked. If the device does
+    /// not match, for example because a firmware update changed its interface layout, an error
+    /// of kind [`ErrorKind::Other`] listing all mismatches is returned. This allows drivers to
+    /// fail early with a clear message instead of with transfer errors later.
+    pub fn validate(&self, expected: &DeviceExpectation) -> Result<()> {
+        let Some(cfg) = self.configuration() else {
+            return Err(Error::new(
+                ErrorKind::Other,
+                "device does not match expectation: device is not configured",
+            ));
+        };
+
+        let mut mismatches = Vec::new();
+        for expected in &expected.interfaces {
+            match cfg.interfaces.iter().find(|iface| iface.interface_number == expected.interface_number) {
+                Some(iface) => expected.mismatches(iface, &mut mismatches),
+                None => mismatches.push(format!("interface {} is missing", expected.interface_number)),
             }
         }
-        configurations
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::new(
+                ErrorKind::Other,
+                format!("device does not match expectation: {}", mismatches.join(", ")),
+            ))
+        }
+    }
+
+    /// Creates a weak handle to this device.
+    ///
+    /// The weak handle does not keep the underlying JavaScript device object alive.
+    pub fn downgrade(&self) -> WeakUsbDevice {
+        WeakUsbDevice { device: js_sys::WeakRef::new(&self.device) }
     }
 
     /// End the device session and relinquish all obtained permissions to
     /// access the USB device.
-    pub async fn forget(self) {
-        JsFuture::from(self.device.forget()).await.unwrap();
+    ///
+    /// Use [`try_forget`](Self::try_forget) to keep the device for retrying on failure.
+    pub async fn forget(self) -> Result<()> {
+        self.try_forget().await
+    }
+
+    /// End the device session and relinquish all obtained permissions to
+    /// access the USB device, without consuming the device.
+    ///
+    /// This allows retrying if forgetting fails. After forgetting succeeded,
+    /// the device is unusable and cannot be opened again.
+    pub async fn try_forget(&self) -> Result<()> {
+        JsFuture::from(self.device.forget()).await?;
+        Ok(())
+    }
+
+    /// Indicates if an [`OpenUsbDevice`] handle to this device exists in this page.
+    ///
+    /// Unlike [`opened`](Self::opened), this is `false` if the device is still open in the
+    /// browser because a dropped handle is being closed or the device was kept open using
+    /// [`OpenUsbDevice::into_device_keep_open`].
+    pub fn opened_handle(&self) -> bool {
+        matches!(session::get(&self.device), Some(Session::Open))
     }
 
     /// Open the USB device to allow USB transfers.
     ///
     /// A device can only be open once.
+    ///
+    /// An error of kind [`ErrorKind::AlreadyOpen`] is returned if a handle to the device exists
+    /// in this page, see [`opened_handle`](Self::opened_handle), or if the browser reports that
+    /// it is in use by another tab or application.
+    /// The latter case can only be detected from the message of the browser's error.
+    ///
+    /// If a handle was dropped without closing it, the device is closed in the background and
+    /// opening waits until that is finished. A device kept open using
+    /// [`OpenUsbDevice::into_device_keep_open`] is taken over without reopening it.
+    /// Likewise, if the returned future is dropped before opening completes, the device is
+    /// closed in the background once the browser has opened it.
     pub async fn open(&self) -> Result<OpenUsbDevice> {
+        match session::get(&self.device) {
+            Some(Session::Open) => {
+                return Err(Error::new(
+                    ErrorKind::AlreadyOpen,
+                    "USB device is already open through another handle in this page",
+                ))
+            }
+            Some(Session::Closing(closing)) => {
+                let _ = JsFuture::from(closing).await;
+            }
+            Some(Session::Detached) => {
+                session::set(&self.device, Session::Open);
+                return Ok(self.handle());
+            }
+            None => (),
+        }
         if self.opened() {
-            return Err(Error::new(ErrorKind::AlreadyOpen, "USB device is already open"));
+            return Err(Error::new(
+                ErrorKind::AlreadyOpen,
+                "USB device is already open without a handle in this page, close or reset it before opening",
+            ));
+        }
+
+        let opening: js_sys::Promise = self.device.open().unchecked_into();
+        session::set(&self.device, Session::Open);
+        let pending = PendingOpen { device: self.device.clone(), opening: Some(opening.clone()) };
+        let res = JsFuture::from(opening).await;
+        pending.finish();
+        if let Err(err) = res {
+            session::remove(&self.device);
+            return Err(Self::open_error(err));
         }
+        Ok(self.handle())
+    }
+
+    /// Handle to this device, which must be open.
+    fn handle(&self) -> OpenUsbDevice {
+        OpenUsbDevice {
+            device: self.clone(),
+            state: Rc::new(HandleState { device: self.device.clone(), closed: Cell::new(false) }),
+            control: Mutex::new(()),
+            claimed: RefCell::new(BTreeSet::new()),
+            transfer_hook: RefCell::new(None),
+            default_read_len: Cell::new(None),
+            out_rate_limit: Cell::new(None),
+            out_next_ms: Cell::new(0.),
+            claim_check: Cell::new(false),
+            carried_in: RefCell::new(BTreeMap::new()),
+        }
+    }
+}
+
+/// Opening of a device in progress, which is closed when the opening future is dropped.
+struct PendingOpen {
+    device: web_sys::UsbDevice,
+    opening: Option<js_sys::Promise>,
+}
+
+impl PendingOpen {
+    /// Marks the opening as completed.
+    fn finish(mut self) {
+        self.opening = None;
+    }
+}
+
+impl Drop for PendingOpen {
+    fn drop(&mut self) {
+        let Some(opening) = self.opening.take() else { return };
+
+        let device = self.device.clone();
+        let closing = wasm_bindgen_futures::future_to_promise(async move {
+            if JsFuture::from(opening).await.is_ok() {
+                let _ = JsFuture::from(device.close()).await;
+            }
+            Ok(JsValue::UNDEFINED)
+        });
+        session::set(&self.device, Session::Closing(closing.clone()));
+
+        let device = self.device.clone();
+        spawn_local(async move {
+            let _ = JsFuture::from(closing.clone()).await;
+            session::remove_closed(&device, &closing);
+        });
+    }
+}
+
+/// Builder for opening a USB device and preparing it for transfers.
+///
+/// Obtained using [`UsbDevice::open_builder`].
+/// The setup steps are performed in the following order: selecting the configuration,
+/// claiming the interfaces and selecting the alternate settings.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct OpenUsbDeviceBuilder {
+    device: UsbDevice,
+    configuration: Option<u8>,
+    claim: Vec<u8>,
+    alternates: Vec<(u8, u8)>,
+}
+
+impl OpenUsbDeviceBuilder {
+    /// Selects the configuration with the specified value.
+    pub fn configuration(mut self, configuration: u8) -> Self {
+        self.configuration = Some(configuration);
+        self
+    }
+
+    /// Claims the specified interface.
+    pub fn claim(mut self, interface: u8) -> Self {
+        self.claim.push(interface);
+        self
+    }
 
-        JsFuture::from(self.device.open()).await?;
-        Ok(OpenUsbDevice { device: self.clone(), closed: false })
+    /// Selects the specified alternate setting of an interface.
+    pub fn alternate(mut self, interface: u8, alternate: u8) -> Self {
+        self.alternates.push((interface, alternate));
+        self
+    }
+
+    /// Opens the USB device and performs the setup steps.
+    ///
+    /// If any step fails, the device is closed again, releasing all interfaces claimed so far.
+    /// The claimed interfaces are released when the returned [`OpenUsbDevice`] is closed or dropped.
+    pub async fn open(self) -> Result<OpenUsbDevice> {
+        let open = self.device.open().await?;
+
+        let setup = async {
+            if let Some(configuration) = self.configuration {
+                open.select_configuration(configuration).await?;
+            }
+            for &interface in &self.claim {
+                open.claim_interface(interface).await?;
+            }
+            for &(interface, alternate) in &self.alternates {
+                open.select_alternate_interface(interface, alternate).await?;
+            }
+            Ok(())
+        };
+
+        match setup.await {
+            Ok(()) => Ok(open),
+            Err(err) => {
+                let _ = open.close().await;
+                Err(err)
+            }
+        }
     }
 }
 
@@ -483,6 +1285,14 @@ impl Hash for UsbDevice {
     }
 }
 
+impl PartialEq for UsbDevice {
+    fn eq(&self, other: &Self) -> bool {
+        self.device == other.device
+    }
+}
+
+impl Eq for UsbDevice {}
+
 impl From<web_sys::UsbDevice> for UsbDevice {
     fn from(device: web_sys::UsbDevice) -> Self {
         Self { device }
@@ -495,6 +1305,74 @@ impl AsRef<web_sys::UsbDevice> for UsbDevice {
     }
 }
 
+/// A weak handle to a USB device.
+///
+/// Unlike [`UsbDevice`], a weak handle does not prevent the garbage collector from
+/// reclaiming the underlying JavaScript device object, for example after the device
+/// has been disconnected. This allows caches to hold devices without keeping them alive.
+///
+/// Obtained using [`UsbDevice::downgrade`].
+#[derive(Clone)]
+pub struct WeakUsbDevice {
+    device: js_sys::WeakRef<web_sys::UsbDevice>,
+}
+
+impl WeakUsbDevice {
+    /// Attempts to obtain the USB device.
+    ///
+    /// Returns `None` if the device object has been garbage collected.
+    pub fn upgrade(&self) -> Option<UsbDevice> {
+        let device = self.device.deref()?;
+        Some(UsbDevice { device })
+    }
+}
+
+/// Identity of a USB device that persists across reconnects and page reloads.
+///
+/// Obtained using [`UsbDevice::identity`] and used with [`Usb::device_by_identity`]
+/// to find a paired device again. Devices without a serial number cannot be told apart
+/// from other devices of the same model.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct UsbDeviceIdentity {
+    /// USB vendor ID.
+    pub vendor_id: u16,
+    /// USB product ID.
+    pub product_id: u16,
+    /// Serial number, if provided by the device.
+    pub serial_number: Option<String>,
+}
+
+impl UsbDeviceIdentity {
+    /// Returns whether the device has this identity.
+    pub fn matches(&self, device: &UsbDevice) -> bool {
+        *self == device.identity()
+    }
+}
+
+impl fmt::Debug for WeakUsbDevice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("WeakUsbDevice").finish()
+    }
+}
+
+/// USB device speed.
+///
+/// Returned by [`UsbDevice::speed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum UsbSpeed {
+    /// The speed could not be determined.
+    Unknown,
+    /// Full speed (12 Mbit/s), supported by USB 1.x devices.
+    Full,
+    /// High speed (480 Mbit/s), supported by USB 2.0 devices.
+    High,
+    /// SuperSpeed (5 Gbit/s or more), supported by USB 3.x devices and later.
+    Super,
+}
+
 /// USB transfer direction.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum UsbDirection {
@@ -504,12 +1382,14 @@ pub enum UsbDirection {
     Out,
 }
 
-impl From<web_sys::UsbDirection> for UsbDirection {
-    fn from(value: web_sys::UsbDirection) -> Self {
+impl TryFrom<web_sys::UsbDirection> for UsbDirection {
+    type Error = Error;
+
+    fn try_from(value: web_sys::UsbDirection) -> Result<Self> {
         match value {
-            web_sys::UsbDirection::In => Self::In,
-            web_sys::UsbDirection::Out => Self::Out,
-            other => unreachable!("unsupported UsbDirection {other:?}"),
+            web_sys::UsbDirection::In => Ok(Self::In),
+            web_sys::UsbDirection::Out => Ok(Self::Out),
+            other => Err(Error::unexpected("transfer direction", &format!("{other:?}").into())),
         }
     }
 }
@@ -523,10 +1403,32 @@ impl From<UsbDirection> for web_sys::UsbDirection {
     }
 }
 
+/// Returns whether interfaces of the specified USB class are protected by WebUSB.
+///
+/// Browsers do not allow claiming interfaces of the following protected classes,
+/// since they are handled by other web APIs or the operating system:
+///
+/// | Class  | Description          |
+/// |--------|----------------------|
+/// | `0x01` | Audio                |
+/// | `0x03` | HID                  |
+/// | `0x08` | Mass storage         |
+/// | `0x0B` | Smart card           |
+/// | `0x0E` | Video                |
+/// | `0x10` | Audio/video          |
+/// | `0xE0` | Wireless controller  |
+///
+/// Attempting to claim such an interface fails with an error of kind [`ErrorKind::Security`].
+pub const fn is_protected_class(class: u8) -> bool {
+    use class_codes::*;
+    matches!(class, AUDIO | HID | MASS_STORAGE | SMART_CARD | VIDEO | AUDIO_VIDEO | WIRELESS_CONTROLLER)
+}
+
 /// A filter used to match specific USB devices by various criteria.
 ///
 /// Fields left as `None` will match any value in that field.
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub struct UsbDeviceFilter {
     /// Optional USB vendor ID.
@@ -591,6 +1493,43 @@ impl UsbDeviceFilter {
         self.serial_number = Some(serial_number.into());
         self
     }
+
+    /// Returns whether this filter targets a protected class, whose interfaces
+    /// cannot be claimed through WebUSB.
+    ///
+    /// See [`is_protected_class`] for details.
+    pub const fn is_likely_blocked(&self) -> bool {
+        match self.class_code {
+            Some(class) => is_protected_class(class),
+            None => false,
+        }
+    }
+
+    /// Returns whether the device matches this filter.
+    ///
+    /// As in WebUSB, the class, subclass and protocol codes match either the device
+    /// or any alternate setting of any interface of the device.
+    pub fn matches(&self, device: &UsbDevice) -> bool {
+        fn matches<T: PartialEq>(filter: &Option<T>, value: T) -> bool {
+            filter.as_ref().is_none_or(|filter| *filter == value)
+        }
+
+        let class_matches = |class: u8, subclass: u8, protocol: u8| {
+            matches(&self.class_code, class)
+                && matches(&self.subclass_code, subclass)
+                && matches(&self.protocol_code, protocol)
+        };
+
+        matches(&self.vendor_id, device.vendor_id())
+            && matches(&self.product_id, device.product_id())
+            && (self.serial_number.is_none() || self.serial_number == device.serial_number())
+            && (class_matches(device.device_class(), device.device_subclass(), device.device_protocol())
+                || device.configurations().iter().any(|cfg| {
+                    cfg.interfaces.iter().flat_map(|iface| &iface.alternates).any(|alt| {
+                        class_matches(alt.interface_class, alt.interface_subclass, alt.interface_protocol)
+                    })
+                }))
+    }
 }
 
 impl From<&UsbDeviceFilter> for web_sys::UsbDeviceFilter {
@@ -618,13 +1557,109 @@ impl From<&UsbDeviceFilter> for web_sys::UsbDeviceFilter {
     }
 }
 
-/// The recipient of a USB control transfer.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum UsbRecipient {
-    /// The request is intended for the USB device as a whole.
-    Device,
-    /// The request is intended for a specific interface on the USB device.
-    Interface,
+/// Descriptors a driver expects a device to have, checked by [`UsbDevice::validate`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DeviceExpectation {
+    /// Expected interfaces of the active configuration.
+    pub interfaces: Vec<InterfaceExpectation>,
+}
+
+impl DeviceExpectation {
+    /// Creates a new, empty device expectation.
+    pub const fn new() -> Self {
+        Self { interfaces: Vec::new() }
+    }
+
+    /// Expects the specified interface.
+    pub fn with_interface(mut self, interface: InterfaceExpectation) -> Self {
+        self.interfaces.push(interface);
+        self
+    }
+}
+
+/// An interface a driver expects a device to have.
+///
+/// Fields left as `None` match any value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct InterfaceExpectation {
+    /// Interface number.
+    pub interface_number: u8,
+    /// Optional interface class code.
+    pub class_code: Option<u8>,
+    /// Optional interface subclass code.
+    pub subclass_code: Option<u8>,
+    /// Optional interface protocol code.
+    pub protocol_code: Option<u8>,
+    /// Endpoints that must be present, given by endpoint number and direction.
+    pub endpoints: Vec<(u8, UsbDirection)>,
+}
+
+impl InterfaceExpectation {
+    /// Expects the interface with the specified number.
+    pub const fn new(interface_number: u8) -> Self {
+        Self {
+            interface_number,
+            class_code: None,
+            subclass_code: None,
+            protocol_code: None,
+            endpoints: Vec::new(),
+        }
+    }
+
+    /// Expects the interface class.
+    pub const fn with_class_code(mut self, class_code: u8) -> Self {
+        self.class_code = Some(class_code);
+        self
+    }
+
+    /// Expects the interface subclass.
+    pub const fn with_subclass_code(mut self, subclass_code: u8) -> Self {
+        self.subclass_code = Some(subclass_code);
+        self
+    }
+
+    /// Expects the interface protocol.
+    pub const fn with_protocol_code(mut self, protocol_code: u8) -> Self {
+        self.protocol_code = Some(protocol_code);
+        self
+    }
+
+    /// Expects the endpoint with the specified number and direction.
+    pub fn with_endpoint(mut self, endpoint: u8, direction: UsbDirection) -> Self {
+        self.endpoints.push((endpoint, direction));
+        self
+    }
+
+    /// Describes how the interface differs from this expectation.
+    fn mismatches(&self, iface: &UsbInterface, mismatches: &mut Vec<String>) {
+        let n = self.interface_number;
+        let alt = &iface.alternate;
+        for (what, expected, actual) in [
+            ("class", self.class_code, alt.interface_class),
+            ("subclass", self.subclass_code, alt.interface_subclass),
+            ("protocol", self.protocol_code, alt.interface_protocol),
+        ] {
+            if let Some(expected) = expected.filter(|&expected| expected != actual) {
+                mismatches.push(format!("interface {n} has {what} {actual:#04x} instead of {expected:#04x}"));
+            }
+        }
+        for &(endpoint, direction) in &self.endpoints {
+            if !alt.endpoints.iter().any(|ep| ep.endpoint_number == endpoint && ep.direction == direction) {
+                mismatches.push(format!("interface {n} lacks {direction:?} endpoint {endpoint}"));
+            }
+        }
+    }
+}
+
+/// The recipient of a USB control transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum UsbRecipient {
+    /// The request is intended for the USB device as a whole.
+    Device,
+    /// The request is intended for a specific interface on the USB device.
+    Interface,
     /// The request is intended for a specific endpoint on the USB device.
     Endpoint,
     /// The request is intended for some other recipient.
@@ -686,7 +1721,7 @@ impl From<&UsbDeviceRequestOptions> for web_sys::UsbDeviceRequestOptions {
 }
 
 /// USB control request.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 #[non_exhaustive]
 pub struct UsbControlRequest {
     /// Whether the request is standard, class-specific or vendor-specific.
@@ -709,6 +1744,67 @@ impl UsbControlRequest {
     ) -> Self {
         Self { request_type, recipient, request, value, index }
     }
+
+    /// Decodes a control request from the 8 bytes of a USB setup packet.
+    ///
+    /// Returns the control request together with the transfer direction and the
+    /// data length (`wLength`) of the transfer.
+    /// Reserved request types and recipients result in an error of kind [`ErrorKind::InvalidAccess`].
+    pub fn from_setup_bytes(bytes: &[u8; 8]) -> Result<(Self, UsbDirection, u16)> {
+        let [request_type, request, value_lo, value_hi, index_lo, index_hi, len_lo, len_hi] = *bytes;
+
+        let direction = if request_type & 0x80 != 0 { UsbDirection::In } else { UsbDirection::Out };
+        let recipient = match request_type & 0x1f {
+            0 => UsbRecipient::Device,
+            1 => UsbRecipient::Interface,
+            2 => UsbRecipient::Endpoint,
+            3 => UsbRecipient::Other,
+            other => return Err(Error::new(ErrorKind::InvalidAccess, format!("reserved recipient {other}"))),
+        };
+        let request_type = match (request_type >> 5) & 0x03 {
+            0 => UsbRequestType::Standard,
+            1 => UsbRequestType::Class,
+            2 => UsbRequestType::Vendor,
+            _ => return Err(Error::new(ErrorKind::InvalidAccess, "reserved request type")),
+        };
+
+        let control = Self::new(
+            request_type,
+            recipient,
+            request,
+            u16::from_le_bytes([value_lo, value_hi]),
+            u16::from_le_bytes([index_lo, index_hi]),
+        );
+        Ok((control, direction, u16::from_le_bytes([len_lo, len_hi])))
+    }
+
+    /// Encodes this control request as the 8 bytes of a USB setup packet.
+    ///
+    /// The direction bit of `bmRequestType` is set according to `direction` and
+    /// `length` is used as the data length (`wLength`) of the transfer.
+    pub const fn to_setup_bytes(&self, direction: UsbDirection, length: u16) -> [u8; 8] {
+        let direction = match direction {
+            UsbDirection::In => 0x80,
+            UsbDirection::Out => 0x00,
+        };
+        let request_type = match self.request_type {
+            UsbRequestType::Standard => 0x00,
+            UsbRequestType::Class => 0x20,
+            UsbRequestType::Vendor => 0x40,
+        };
+        let recipient = match self.recipient {
+            UsbRecipient::Device => 0x00,
+            UsbRecipient::Interface => 0x01,
+            UsbRecipient::Endpoint => 0x02,
+            UsbRecipient::Other => 0x03,
+        };
+
+        let [value_lo, value_hi] = self.value.to_le_bytes();
+        let [index_lo, index_hi] = self.index.to_le_bytes();
+        let [len_lo, len_hi] = length.to_le_bytes();
+        let bm_request_type = direction | request_type | recipient;
+        [bm_request_type, self.request, value_lo, value_hi, index_lo, index_hi, len_lo, len_hi]
+    }
 }
 
 impl From<&UsbControlRequest> for web_sys::UsbControlTransferParameters {
@@ -717,6 +1813,103 @@ impl From<&UsbControlRequest> for web_sys::UsbControlTransferParameters {
     }
 }
 
+/// Result of a transfer from device to host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct InTransferResult {
+    /// Received data.
+    pub data: Vec<u8>,
+    /// Whether the device sent less data than requested.
+    ///
+    /// Many protocols use such a short packet to indicate the end of a message.
+    pub is_short: bool,
+}
+
+impl InTransferResult {
+    fn new(data: &js_sys::DataView, requested: u32) -> Self {
+        let data = data_view_to_vec(data);
+        Self { is_short: (data.len() as u64) < u64::from(requested), data }
+    }
+
+    /// The received data as an array of `N` bytes.
+    fn array<const N: usize>(&self) -> Result<[u8; N]> {
+        self.data.as_slice().try_into().map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("USB device sent {} bytes, but {N} bytes were expected", self.data.len()),
+            )
+        })
+    }
+
+    /// Decodes the received data as a little endian `u16`.
+    ///
+    /// An error of kind [`ErrorKind::InvalidData`] is returned unless exactly two bytes were received.
+    pub fn as_u16_le(&self) -> Result<u16> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    /// Decodes the received data as a big endian `u16`.
+    ///
+    /// An error of kind [`ErrorKind::InvalidData`] is returned unless exactly two bytes were received.
+    pub fn as_u16_be(&self) -> Result<u16> {
+        self.array().map(u16::from_be_bytes)
+    }
+
+    /// Decodes the received data as a little endian `u32`.
+    ///
+    /// An error of kind [`ErrorKind::InvalidData`] is returned unless exactly four bytes were received.
+    pub fn as_u32_le(&self) -> Result<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    /// Decodes the received data as a big endian `u32`.
+    ///
+    /// An error of kind [`ErrorKind::InvalidData`] is returned unless exactly four bytes were received.
+    pub fn as_u32_be(&self) -> Result<u32> {
+        self.array().map(u32::from_be_bytes)
+    }
+}
+
+/// Copies the bytes referenced by a `DataView` into a vector.
+fn data_view_to_vec(data: &js_sys::DataView) -> Vec<u8> {
+    Uint8Array::new_with_byte_offset_and_length(
+        &data.buffer(),
+        data.byte_offset() as u32,
+        data.byte_length() as u32,
+    )
+    .to_vec()
+}
+
+/// Data of an IN endpoint that was not consumed by the transfer it was received by.
+enum CarriedIn {
+    /// A transfer of the specified length that is still pending.
+    Pending(Promise<web_sys::UsbInTransferResult>, u32),
+    /// Received data exceeding the length requested by the reader.
+    Surplus(js_sys::DataView),
+}
+
+/// Result of a single packet of an isochronous transfer from device to host.
+pub type IsochronousInPacket = Result<Vec<u8>>;
+
+/// Description of a completed transfer, passed to the hook set by
+/// [`OpenUsbDevice::set_transfer_hook`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TransferLog {
+    /// Transfer direction.
+    pub direction: UsbDirection,
+    /// Endpoint number, which is 0 for control transfers.
+    pub endpoint: u8,
+    /// Number of bytes requested for transfers from the device or provided for
+    /// transfers to the device.
+    pub length: u32,
+    /// Number of bytes actually transferred or the error that occurred.
+    pub result: Result<u32>,
+}
+
+/// Hook invoked after each transfer of an [`OpenUsbDevice`].
+type TransferHook = Rc<dyn Fn(&TransferLog)>;
+
 /// WebUSB event.
 #[derive(Debug, Clone)]
 #[non_exhaustive]
@@ -727,6 +1920,87 @@ pub enum UsbEvent {
     Disconnected(UsbDevice),
 }
 
+/// State of a device tracked by [`Usb::persistent_device`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum DeviceState {
+    /// A matching device was connected and has been opened.
+    Connected(OpenUsbDevice),
+    /// The device was disconnected.
+    Disconnected,
+}
+
+/// Manages the connection lifecycle of a device matching a filter.
+///
+/// This combines reconnecting, opening and claiming of all interfaces, which is what
+/// most applications need to keep talking to their device.
+#[derive(Debug)]
+pub struct ManagedDevice<'a> {
+    usb: &'a Usb,
+    filter: UsbDeviceFilter,
+}
+
+impl<'a> ManagedDevice<'a> {
+    /// Manages the device matching `filter` using the WebUSB API `usb`.
+    pub fn new(usb: &'a Usb, filter: UsbDeviceFilter) -> Self {
+        Self { usb, filter }
+    }
+
+    /// Connection state of the device, opened and with all interfaces claimed.
+    ///
+    /// The stream yields `Some` with a fresh handle each time a matching device connects, starting
+    /// with an already attached one, and `None` when that device disconnects. The handle is shared
+    /// using an [`Rc`], since [`OpenUsbDevice`] cannot be sent between threads. Once the device
+    /// disconnects, transfers on the handle fail and it should be dropped, which closes it when no
+    /// clones remain.
+    ///
+    /// Devices that fail to open or to claim their interfaces are skipped until they connect again.
+    /// Failing to claim the interfaces is logged to the console as a warning.
+    /// See [`Usb::persistent_device`] for details.
+    pub fn connection(&self) -> impl Stream<Item = Option<Rc<OpenUsbDevice>>> + '_ {
+        let connected = Rc::new(Cell::new(false));
+        self.usb.persistent_device(self.filter.clone()).filter_map(move |state| {
+            let connected = connected.clone();
+            async move {
+                match state {
+                    DeviceState::Connected(open) => match open.claim_all_interfaces().await {
+                        Ok(_) => {
+                            connected.set(true);
+                            Some(Some(Rc::new(open)))
+                        }
+                        Err(err) => {
+                            web_sys::console::warn_1(
+                                &format!(
+                                    "webusb-web: cannot claim interfaces of device {:04x}:{:04x}: {err}",
+                                    open.device().vendor_id(),
+                                    open.device().product_id()
+                                )
+                                .into(),
+                            );
+                            None
+                        }
+                    },
+                    DeviceState::Disconnected => connected.replace(false).then_some(None),
+                }
+            }
+        })
+    }
+}
+
+/// Watch started by [`Usb::auto_connect`].
+///
+/// Dropping this stops watching. Devices already passed to the handler are not affected.
+#[derive(Debug)]
+#[must_use = "dropping the handle stops watching for devices"]
+pub struct AutoConnect {
+    _stop: oneshot::Sender<()>,
+}
+
+impl AutoConnect {
+    /// Stops watching for devices.
+    pub fn stop(self) {}
+}
+
 /// Wrapper for making any type [Send].
 #[derive(Debug, Clone)]
 struct SendWrapper<T>(pub T);
@@ -740,9 +2014,13 @@ pub struct UsbEvents {
     // BroadcastStream. However, we need to ensure that UsbEvents
     // is !Send.
     rx: BroadcastStream<SendWrapper<UsbEvent>>,
+    lag_handler: Rc<RefCell<Option<LagHandler>>>,
     _marker: PhantomData<*const ()>,
 }
 
+/// Handler invoked when an event stream has lost events.
+type LagHandler = Box<dyn FnMut()>;
+
 impl fmt::Debug for UsbEvents {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_tuple("UsbEvents").finish()
@@ -755,19 +2033,41 @@ impl Stream for UsbEvents {
         loop {
             match ready!(self.rx.poll_next_unpin(cx)) {
                 Some(Ok(event)) => break Poll::Ready(Some(event.0)),
-                Some(Err(BroadcastStreamRecvError::Lagged(_))) => (),
+                Some(Err(BroadcastStreamRecvError::Lagged(_))) => {
+                    // The handler is taken out while it runs, so that it may replace itself.
+                    let handler = self.lag_handler.borrow_mut().take();
+                    if let Some(mut handler) = handler {
+                        handler();
+                        self.lag_handler.borrow_mut().get_or_insert(handler);
+                    }
+                }
                 None => break Poll::Ready(None),
             }
         }
     }
 }
 
+/// JavaScript execution context the WebUSB API was obtained from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum UsbContext {
+    /// Browser window.
+    Window,
+    /// Web worker.
+    Worker,
+    /// Unknown global context.
+    Unknown,
+}
+
 /// WebUSB device enumeration and connection.
 pub struct Usb {
     usb: web_sys::Usb,
+    context: UsbContext,
     event_rx: broadcast::Receiver<SendWrapper<UsbEvent>>,
     on_connect: Closure<dyn Fn(web_sys::UsbConnectionEvent)>,
     on_disconnect: Closure<dyn Fn(web_sys::UsbConnectionEvent)>,
+    lag_handler: Rc<RefCell<Option<LagHandler>>>,
+    tracked: RefCell<Vec<rc::Weak<HandleState>>>,
 }
 
 impl fmt::Debug for Usb {
@@ -779,7 +2079,16 @@ impl fmt::Debug for Usb {
 impl Usb {
     /// Checks that WebUSB is available and obtains access to the WebUSB API.
     pub fn new() -> Result<Self> {
-        let usb = Self::browser_usb()?;
+        Self::new_in(&js_sys::global())
+    }
+
+    /// Obtains access to the WebUSB API provided as `navigator.usb` by the specified global object.
+    ///
+    /// This supports JavaScript hosts exposing WebUSB on a non-standard global object
+    /// as well as testing using a stubbed WebUSB API. The [context](Self::context) is
+    /// [`UsbContext::Unknown`] if the object is neither a window nor a worker.
+    pub fn new_in(global: &JsValue) -> Result<Self> {
+        let (usb, context) = Self::browser_usb(global)?;
 
         let (event_tx, event_rx) = broadcast::channel(1024);
 
@@ -799,56 +2108,451 @@ impl Usb {
         };
         usb.add_event_listener_with_callback("disconnect", on_disconnect.as_ref().unchecked_ref()).unwrap();
 
-        Ok(Self { usb, event_rx, on_connect, on_disconnect })
+        Ok(Self {
+            usb,
+            context,
+            event_rx,
+            on_connect,
+            on_disconnect,
+            lag_handler: Default::default(),
+            tracked: Default::default(),
+        })
     }
 
-    fn browser_usb() -> Result<web_sys::Usb> {
-        let global = js_sys::global();
-
-        if let Some(window) = global.dyn_ref::<web_sys::Window>() {
-            let navigator = window.navigator();
-            match Reflect::get(&navigator, &JsValue::from_str("usb")) {
-                Ok(usb) if !usb.is_null() && !usb.is_undefined() => return Ok(navigator.usb()),
-                _ => (),
-            }
-        }
+    fn browser_usb(global: &JsValue) -> Result<(web_sys::Usb, UsbContext)> {
+        let context = if global.has_type::<web_sys::Window>() {
+            UsbContext::Window
+        } else if global.has_type::<web_sys::WorkerGlobalScope>() {
+            UsbContext::Worker
+        } else {
+            UsbContext::Unknown
+        };
 
-        if let Some(worker) = global.dyn_ref::<web_sys::WorkerGlobalScope>() {
-            let navigator = worker.navigator();
-            match Reflect::get(&navigator, &JsValue::from_str("usb")) {
-                Ok(usb) if !usb.is_null() && !usb.is_undefined() => return Ok(navigator.usb()),
-                _ => (),
-            }
+        let usb = Reflect::get(global, &JsValue::from_str("navigator"))
+            .ok()
+            .filter(|navigator| navigator.is_object())
+            .and_then(|navigator| Reflect::get(&navigator, &JsValue::from_str("usb")).ok());
+        match usb {
+            Some(usb) if !usb.is_null() && !usb.is_undefined() => Ok((usb.unchecked_into(), context)),
+            _ => Err(Error::new(ErrorKind::Unsupported, "browser does not support WebUSB")),
         }
+    }
 
-        Err(Error::new(ErrorKind::Unsupported, "browser does not support WebUSB"))
+    /// The JavaScript execution context the WebUSB API was obtained from.
+    pub fn context(&self) -> UsbContext {
+        self.context
     }
 
     /// Subscribe to a stream of [`UsbEvent`]s notifying of USB device changes.
     ///
     /// Only events for paired devices will be provided.
     pub fn events(&self) -> UsbEvents {
-        UsbEvents { rx: self.event_rx.resubscribe().into(), _marker: PhantomData }
+        UsbEvents {
+            rx: self.event_rx.resubscribe().into(),
+            lag_handler: self.lag_handler.clone(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets a handler that is invoked when an event stream has lost events.
+    ///
+    /// Events are dropped if an event stream obtained from this object is not polled
+    /// fast enough. The device list maintained by the application may then be inconsistent
+    /// and should be refreshed using [`devices`](Self::devices).
+    ///
+    /// The handler is invoked synchronously while the lagging stream is polled, once for
+    /// each occurrence of lost events. A previously set handler is replaced, also when
+    /// this method is called from within the handler.
+    pub fn on_lag(&self, f: impl FnMut() + 'static) {
+        *self.lag_handler.borrow_mut() = Some(Box::new(f));
+    }
+
+    /// Tracks an open device, so that it is closed by [`close_all`](Self::close_all).
+    ///
+    /// Only a weak reference to the handle is kept, thus tracking does not keep the device open.
+    /// When the handle is dropped, it closes the device as usual and tracking ends.
+    pub fn track(&self, device: &OpenUsbDevice) {
+        let mut tracked = self.tracked.borrow_mut();
+        tracked.retain(|state| state.strong_count() > 0);
+        tracked.push(Rc::downgrade(&device.state));
+    }
+
+    /// Closes all devices tracked using [`track`](Self::track) whose handles still exist.
+    ///
+    /// This allows cleanly closing all devices on application teardown, for example when the
+    /// page is about to be unloaded, without relying on dropping each handle, which closes
+    /// the device in the background and thus may not complete.
+    /// The handles are closed as by [`OpenUsbDevice::close`]: they remain valid objects, but
+    /// transfers on them fail and dropping them does not close the device again. The devices
+    /// can be opened again using [`UsbDevice::open`].
+    ///
+    /// All devices are closed concurrently. If closing fails for any of them, the first
+    /// error is returned after all have been attempted.
+    pub async fn close_all(&self) -> Result<()> {
+        let handles: Vec<_> = self
+            .tracked
+            .take()
+            .into_iter()
+            .filter_map(|state| state.upgrade())
+            .filter(|state| !state.closed.get())
+            .collect();
+        let results = future::join_all(handles.iter().map(|state| JsFuture::from(state.start_close()))).await;
+        results.into_iter().find_map(|res| res.err()).map_or(Ok(()), |err| Err(err.into()))
+    }
+
+    /// Subscribe to WebUSB events, starting with the currently attached devices.
+    ///
+    /// The stream first yields a synthetic [`UsbEvent::Connected`] event for each
+    /// paired attached device, as returned by [`devices`](Self::devices), and then
+    /// continues with live events like [`events`](Self::events).
+    ///
+    /// Live events are subscribed to before the device list is obtained, thus no event is missed.
+    /// However, a device connected meanwhile may be reported twice.
+    pub fn events_with_current(&self) -> impl Stream<Item = UsbEvent> + '_ {
+        let live = self.events();
+        stream::once(self.devices()).flat_map(stream::iter).map(UsbEvent::Connected).chain(live)
+    }
+
+    /// Subscribe to WebUSB events with an additional buffer for up to `extra_buffer` events.
+    ///
+    /// All event streams share the capacity of one channel, so a stream that is polled slowly
+    /// loses events once that capacity is exhausted. This stream is backed by a task that
+    /// moves events from the shared channel into a buffer owned by this stream, giving a
+    /// slow consumer its own backlog before it lags.
+    ///
+    /// The shared channel still applies once the extra buffer is full, thus at most
+    /// `extra_buffer` plus the shared capacity of events are retained. Lost events are
+    /// reported to the handler set by [`on_lag`](Self::on_lag).
+    /// The task ends when the stream is dropped.
+    pub fn events_buffered(&self, extra_buffer: usize) -> impl Stream<Item = UsbEvent> {
+        let mut events = self.events();
+        let (tx, mut rx) = mpsc::channel(extra_buffer.max(1));
+
+        spawn_local(async move {
+            loop {
+                let event = match future::select(pin!(events.next()), pin!(tx.closed())).await {
+                    Either::Left((Some(event), _)) => event,
+                    _ => break,
+                };
+                if tx.send(event).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        stream::poll_fn(move |cx| rx.poll_recv(cx))
+    }
+
+    /// Polls the list of paired attached devices and synthesizes events from its changes.
+    ///
+    /// This is a fallback event source for environments where the `connect` and `disconnect`
+    /// events of WebUSB are not fired reliably.
+    /// Every `interval` the list returned by [`devices`](Self::devices) is compared to the
+    /// previous one, yielding [`UsbEvent::Disconnected`] for each device that disappeared and
+    /// [`UsbEvent::Connected`] for each device that appeared.
+    /// The first list is obtained when the stream is first polled and serves as the baseline;
+    /// no events are yielded for it.
+    ///
+    /// Only changes of paired devices are detected, since unpaired devices are never listed.
+    pub fn with_polling(&self, interval: Duration) -> impl Stream<Item = UsbEvent> + '_ {
+        stream::unfold(None, move |prev: Option<Vec<UsbDevice>>| async move {
+            if prev.is_some() {
+                time::sleep(interval).await;
+            }
+            let current = self.devices().await;
+            let events = prev.map(|prev| Self::diff_devices(&prev, &current)).unwrap_or_default();
+            Some((stream::iter(events), Some(current)))
+        })
+        .flatten()
+    }
+
+    /// Events describing the change from the device list `prev` to `current`.
+    fn diff_devices(prev: &[UsbDevice], current: &[UsbDevice]) -> Vec<UsbEvent> {
+        let disconnected = prev.iter().filter(|dev| !current.contains(dev)).cloned().map(UsbEvent::Disconnected);
+        let connected = current.iter().filter(|dev| !prev.contains(dev)).cloned().map(UsbEvent::Connected);
+        disconnected.chain(connected).collect()
+    }
+
+    /// Keeps a device matching the filter open across disconnects and reconnects.
+    ///
+    /// The stream yields [`DeviceState::Connected`] with the opened device whenever a matching
+    /// device is attached and [`DeviceState::Disconnected`] when it goes away. An already
+    /// attached matching device is opened immediately. After a disconnect, the next matching
+    /// device that connects is opened automatically.
+    ///
+    /// Re-opening requires that the device stays paired, since no permission is requested.
+    /// Use [`request_device`](Self::request_device) to pair the device beforehand.
+    /// Devices that fail to open are skipped until they connect again.
+    pub fn persistent_device(&self, filter: UsbDeviceFilter) -> impl Stream<Item = DeviceState> + '_ {
+        let events = self.events();
+        stream::unfold((events, None, true), move |(mut events, current, initial)| {
+            let filter = filter.clone();
+            async move {
+                if initial {
+                    for dev in self.devices().await.into_iter().filter(|dev| filter.matches(dev)) {
+                        if let Ok(open) = dev.open().await {
+                            return Some((DeviceState::Connected(open), (events, Some(dev), false)));
+                        }
+                    }
+                }
+
+                loop {
+                    match (events.next().await?, &current) {
+                        (UsbEvent::Disconnected(dev), Some(cur)) if dev == *cur => {
+                            return Some((DeviceState::Disconnected, (events, None, false)));
+                        }
+                        (UsbEvent::Connected(dev), None) if filter.matches(&dev) => {
+                            if let Ok(open) = dev.open().await {
+                                return Some((DeviceState::Connected(open), (events, Some(dev), false)));
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+            }
+        })
+    }
+
+    /// Opens each matching device when it is connected and passes it to `handler`.
+    ///
+    /// Matching devices that are already attached are opened first, followed by devices
+    /// connected later. Devices that are already open are skipped. This is the callback-based
+    /// counterpart of [`persistent_device`](Self::persistent_device) and likewise requires
+    /// devices to be paired beforehand.
+    ///
+    /// Opening a device may fail, in which case a warning is logged to the console and
+    /// the device is skipped until it connects again.
+    ///
+    /// Watching stops when the returned handle is dropped.
+    pub fn auto_connect(
+        &self, filter: UsbDeviceFilter, mut handler: impl FnMut(OpenUsbDevice) + 'static,
+    ) -> AutoConnect {
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let mut events = self.events();
+        let usb = self.usb.clone();
+
+        spawn_local(async move {
+            let watch = async move {
+                let mut connect = async |dev: UsbDevice| {
+                    if !filter.matches(&dev) || dev.opened() {
+                        return;
+                    }
+                    match dev.open().await {
+                        Ok(open) => handler(open),
+                        Err(err) => web_sys::console::warn_1(
+                            &format!(
+                                "webusb-web: cannot open device {:04x}:{:04x} for auto connect: {err}",
+                                dev.vendor_id(),
+                                dev.product_id()
+                            )
+                            .into(),
+                        ),
+                    }
+                };
+
+                match Self::list_devices(&usb).await {
+                    Ok(devices) => {
+                        for dev in devices {
+                            connect(dev).await;
+                        }
+                    }
+                    Err(err) => web_sys::console::warn_1(
+                        &format!("webusb-web: cannot list devices for auto connect: {err}").into(),
+                    ),
+                }
+                while let Some(event) = events.next().await {
+                    if let UsbEvent::Connected(dev) = event {
+                        connect(dev).await;
+                    }
+                }
+            };
+            future::select(pin!(watch), stop_rx).await;
+        });
+
+        AutoConnect { _stop: stop_tx }
     }
 
     /// List of paired attached devices.
     ///
     /// For information on pairing devices, see [`request_device`](Self::request_device).
+    ///
+    /// If the browser fails to provide the list, the failure is logged to the console
+    /// and an empty list is returned. Use [`try_devices`](Self::try_devices) to distinguish
+    /// this from the absence of paired devices.
     pub async fn devices(&self) -> Vec<UsbDevice> {
-        let list = JsFuture::from(self.usb.get_devices()).await.unwrap();
-        js_sys::Array::from(&list)
+        match self.try_devices().await {
+            Ok(devices) => devices,
+            Err(err) => {
+                web_sys::console::error_1(&format!("webusb-web: cannot list devices: {err}").into());
+                Vec::new()
+            }
+        }
+    }
+
+    /// List of paired attached devices, failing if the browser cannot provide it.
+    ///
+    /// An error is returned if listing the devices fails or the browser returns a value
+    /// of unexpected type, the latter being of kind [`ErrorKind::Other`].
+    pub async fn try_devices(&self) -> Result<Vec<UsbDevice>> {
+        Self::list_devices(&self.usb).await
+    }
+
+    /// List of paired attached devices provided by the WebUSB API `usb`.
+    async fn list_devices(usb: &web_sys::Usb) -> Result<Vec<UsbDevice>> {
+        let list = JsFuture::from(usb.get_devices()).await?;
+        cast::<js_sys::Array>(list, "device list")?
             .iter()
-            .map(|dev| UsbDevice::from(dev.dyn_into::<web_sys::UsbDevice>().unwrap()))
+            .map(|dev| Ok(cast::<web_sys::UsbDevice>(dev, "device")?.into()))
             .collect()
     }
 
+    /// List of paired attached devices, waiting up to `timeout` for it to become non-empty.
+    ///
+    /// Shortly after page load the browser may not have enumerated the paired devices yet.
+    /// This polls [`devices`](Self::devices) until it returns a device or the timeout elapses
+    /// and then returns the latest list.
+    /// An empty list is normal if no device is paired or attached.
+    pub async fn devices_ready(&self, timeout: Duration) -> Vec<UsbDevice> {
+        const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+        let deadline = Deadline::after(timeout);
+        loop {
+            let devices = self.devices().await;
+            if !devices.is_empty() || deadline.has_passed() {
+                return devices;
+            }
+            time::sleep(POLL_INTERVAL.min(deadline.remaining())).await;
+        }
+    }
+
+    /// Paired attached devices grouped by their vendor identifier.
+    ///
+    /// Within each group the devices are in the order returned by [`devices`](Self::devices).
+    pub async fn devices_by_vendor(&self) -> BTreeMap<u16, Vec<UsbDevice>> {
+        let mut groups: BTreeMap<u16, Vec<UsbDevice>> = BTreeMap::new();
+        for dev in self.devices().await {
+            groups.entry(dev.vendor_id()).or_default().push(dev);
+        }
+        groups
+    }
+
+    /// Finds the paired attached device with the specified identity.
+    ///
+    /// This allows reconnecting to a device after a page reload without prompting the user,
+    /// provided that the device is still paired. If multiple devices match, the first is returned.
+    pub async fn device_by_identity(&self, identity: &UsbDeviceIdentity) -> Option<UsbDevice> {
+        self.devices().await.into_iter().find(|dev| identity.matches(dev))
+    }
+
     /// Pairs a USB device with the specified filter criteria.
     ///
     /// Calling this function triggers the user agent's pairing flow.
+    ///
+    /// The returned future may be dropped at any time. The browser's chooser cannot be
+    /// closed programmatically, but its outcome is then discarded.
+    ///
+    /// This must be called from a user gesture, such as a click handler.
+    /// Otherwise an error of kind [`ErrorKind::Security`] is returned.
+    /// If the user closes the chooser without selecting a device, an error of
+    /// kind [`ErrorKind::NoDeviceSelected`] is returned.
     pub async fn request_device(&self, filters: impl IntoIterator<Item = UsbDeviceFilter>) -> Result<UsbDevice> {
         let opts = &UsbDeviceRequestOptions::new(filters);
-        let dev = JsFuture::from(self.usb.request_device(&opts.into())).await?;
-        Ok(dev.dyn_into::<web_sys::UsbDevice>().unwrap().into())
+        let dev = JsFuture::from(self.usb.request_device(&opts.into())).await.map_err(Self::request_error)?;
+        Ok(cast::<web_sys::UsbDevice>(dev, "device")?.into())
+    }
+
+    /// Converts a JavaScript exception raised by `requestDevice` into an error.
+    ///
+    /// The browser only reports a missing user gesture as a generic security error
+    /// and a dismissed chooser as a not found error.
+    /// Since a permissions policy blocking WebUSB also raises a security error, a missing
+    /// user gesture is only diagnosed if the message of the browser indicates it.
+    fn request_error(value: JsValue) -> Error {
+        let mut err = Error::from(value);
+        match err.kind {
+            ErrorKind::Security => {
+                let msg = err.msg.to_lowercase();
+                err.msg = if ["gesture", "activation"].iter().any(|pat| msg.contains(pat)) {
+                    format!("requestDevice must be called from a user gesture ({})", err.msg)
+                } else {
+                    format!(
+                        "requestDevice was denied, which may be caused by a missing user gesture \
+                         or a permissions policy ({})",
+                        err.msg
+                    )
+                }
+            }
+            ErrorKind::Disconnected => err.kind = ErrorKind::NoDeviceSelected,
+            _ => (),
+        }
+        err
+    }
+
+    /// Pairs a USB device with the specified filter criteria, allowing the user to retry
+    /// if the chooser was dismissed.
+    ///
+    /// Showing the chooser requires a user gesture for each attempt, thus `gesture` is
+    /// called before every attempt and must complete during a user gesture, for example
+    /// when the user clicks a "try again" button. Browsers grant a short period of user
+    /// activation after the gesture, within which the chooser is shown. If the future
+    /// completes without a user gesture, the attempt fails with an error of kind
+    /// [`ErrorKind::Security`].
+    ///
+    /// Up to `attempts` attempts are made while the user closes the chooser without selecting
+    /// a device. Then the error of kind [`ErrorKind::NoDeviceSelected`] of the last attempt
+    /// is returned. Other errors are returned immediately.
+    pub async fn request_device_retry<F: Future<Output = ()>>(
+        &self, filters: impl IntoIterator<Item = UsbDeviceFilter>, attempts: usize,
+        mut gesture: impl FnMut() -> F,
+    ) -> Result<UsbDevice> {
+        let filters: Vec<_> = filters.into_iter().collect();
+        let mut last_err = Error::new(ErrorKind::NoDeviceSelected, "no attempt to request a device was made");
+        for _ in 0..attempts {
+            gesture().await;
+            match self.request_device(filters.clone()).await {
+                Err(err) if err.kind == ErrorKind::NoDeviceSelected => last_err = err,
+                res => return res,
+            }
+        }
+        Err(last_err)
+    }
+
+    /// Pairs a USB device with the specified filter criteria, unless cancelled.
+    ///
+    /// This behaves like [`request_device`](Self::request_device), but gives up as soon as
+    /// `cancel` completes, for example on a route change of a single-page application.
+    /// In this case an error of kind [`ErrorKind::Cancelled`] is returned.
+    pub async fn request_device_cancellable(
+        &self, filters: impl IntoIterator<Item = UsbDeviceFilter>, cancel: impl Future<Output = ()>,
+    ) -> Result<UsbDevice> {
+        match future::select(pin!(self.request_device(filters)), pin!(cancel)).await {
+            Either::Left((res, _)) => res,
+            Either::Right(((), _)) => Err(Error::new(ErrorKind::Cancelled, "device request was cancelled")),
+        }
+    }
+
+    /// Pairs a USB device matching the filter and opens it.
+    ///
+    /// This combines [`request_device`](Self::request_device) and [`UsbDevice::open`],
+    /// which is the most common flow when using a device for the first time.
+    /// If opening fails, the device stays paired and the error of [`UsbDevice::open`] is returned.
+    /// The device can be obtained again from [`devices`](Self::devices).
+    pub async fn request_and_open(&self, filter: UsbDeviceFilter) -> Result<OpenUsbDevice> {
+        let device = self.request_device([filter]).await?;
+        device.open().await
+    }
+
+    /// Opens the first paired attached device matching the filter.
+    ///
+    /// Returns `Ok(None)` if no paired device matches. This never triggers the pairing
+    /// flow and thus does not require a user gesture, making it suitable for connecting
+    /// automatically to a previously paired device.
+    /// If opening the matching device fails, its error is returned.
+    pub async fn open_first_matching(&self, filter: UsbDeviceFilter) -> Result<Option<OpenUsbDevice>> {
+        match self.devices().await.into_iter().find(|dev| filter.matches(dev)) {
+            Some(device) => Ok(Some(device.open().await?)),
+            None => Ok(None),
+        }
     }
 }
 
@@ -866,9 +2570,49 @@ impl Drop for Usb {
 /// An opened USB device.
 ///
 /// Dropping this causes the USB device to be closed.
+///
+/// Control transfers are always serialized, since WebUSB does not allow
+/// multiple control transfers to be in flight at the same time.
+/// Concurrently issued control transfers are queued and performed in order.
 pub struct OpenUsbDevice {
     device: UsbDevice,
-    closed: bool,
+    /// Shared with [`Usb::track`], which references it weakly.
+    state: Rc<HandleState>,
+    control: Mutex<()>,
+    claimed: RefCell<BTreeSet<u8>>,
+    transfer_hook: RefCell<Option<TransferHook>>,
+    default_read_len: Cell<Option<u32>>,
+    out_rate_limit: Cell<Option<u32>>,
+    out_next_ms: Cell<f64>,
+    claim_check: Cell<bool>,
+    /// IN data not consumed by the transfer it was received by, by endpoint number.
+    carried_in: RefCell<BTreeMap<u8, VecDeque<CarriedIn>>>,
+}
+
+/// State of an [`OpenUsbDevice`] that is shared with [`Usb::track`].
+struct HandleState {
+    device: web_sys::UsbDevice,
+    closed: Cell<bool>,
+}
+
+impl HandleState {
+    /// Starts closing the device and returns the promise of the browser.
+    ///
+    /// The device session is marked as closing until the promise settles.
+    fn start_close(&self) -> js_sys::Promise {
+        self.closed.set(true);
+        let closing: js_sys::Promise = self.device.close().unchecked_into();
+        session::set(&self.device, Session::Closing(closing.clone()));
+
+        let device = self.device.clone();
+        let done = closing.clone();
+        spawn_local(async move {
+            let _ = JsFuture::from(done.clone()).await;
+            session::remove_closed(&device, &done);
+        });
+
+        closing
+    }
 }
 
 impl fmt::Debug for OpenUsbDevice {
@@ -893,46 +2637,349 @@ impl OpenUsbDevice {
         &self.device
     }
 
+    /// Runs the provided future until it completes or this device is disconnected.
+    ///
+    /// This is useful for making long-running operations, such as transfers, fail promptly
+    /// when the device goes away. Disconnection is detected using the event stream of `usb`,
+    /// which thus must be kept alive while the future runs. If the disconnect event arrives
+    /// first, the future is dropped and an error of kind [`ErrorKind::Disconnected`] is returned.
+    pub async fn with_disconnect<F, T>(&self, usb: &Usb, fut: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        match future::select(pin!(fut), pin!(self.disconnected(usb))).await {
+            Either::Left((res, _)) => res,
+            Either::Right(((), _)) => Err(Error::new(ErrorKind::Disconnected, "USB device was disconnected")),
+        }
+    }
+
+    /// Resolves when this device is disconnected.
+    ///
+    /// Disconnection is detected using the event stream of `usb`, which thus must be kept
+    /// alive while waiting. The event stream is subscribed to when this method is called,
+    /// so a disconnection occurring before the returned future is first polled is not missed.
+    pub fn disconnected(&self, usb: &Usb) -> impl Future<Output = ()> + 'static {
+        let mut events = usb.events();
+        let device = self.device.clone();
+        async move {
+            while let Some(event) = events.next().await {
+                if matches!(&event, UsbEvent::Disconnected(dev) if *dev == device) {
+                    return;
+                }
+            }
+            pending().await
+        }
+    }
+
+    /// Maximum packet size of the specified endpoint.
+    ///
+    /// The endpoint is searched in the active alternate settings of the claimed interfaces
+    /// of the active configuration. Returns `None` if no such endpoint exists.
+    pub fn max_packet_size(&self, endpoint: u8, direction: UsbDirection) -> Option<u32> {
+        let cfg = self.device.configuration()?;
+        cfg.interfaces
+            .iter()
+            .filter(|iface| iface.claimed)
+            .flat_map(|iface| &iface.alternate.endpoints)
+            .find(|ep| ep.endpoint_number == endpoint && ep.direction == direction)
+            .map(|ep| ep.packet_size)
+    }
+
     /// Releases all open interfaces and ends the device session.
     ///
     /// It is not necessary to call this method, since dropping
     /// [OpenUsbDevice] will also close the USB device.
-    pub async fn close(mut self) -> Result<()> {
-        self.closed = true;
-        JsFuture::from(self.dev().close()).await?;
+    pub async fn close(self) -> Result<()> {
+        JsFuture::from(self.state.start_close()).await?;
         Ok(())
     }
 
-    /// Resets the device and cancels all pending operations.
-    pub async fn reset(&self) -> Result<()> {
-        JsFuture::from(self.dev().reset()).await?;
-        Ok(())
+    /// Ends the device session and returns the device.
+    ///
+    /// The device stays paired and can be opened again using [`UsbDevice::open`].
+    pub async fn close_and_into_device(self) -> Result<UsbDevice> {
+        JsFuture::from(self.state.start_close()).await?;
+        Ok(self.device.clone())
     }
 
-    /// Selects the USB device configuration with the specified index.
-    pub async fn select_configuration(&self, configuration: u8) -> Result<()> {
-        JsFuture::from(self.dev().select_configuration(configuration)).await?;
-        Ok(())
+    /// Returns the device without closing it.
+    ///
+    /// Unlike dropping, this leaves the device session open in the browser, including
+    /// its claimed interfaces. Use [`UsbDevice::open`] to obtain a new handle to it later.
+    pub fn into_device_keep_open(self) -> UsbDevice {
+        self.state.closed.set(true);
+        session::set(self.dev(), Session::Detached);
+        self.device.clone()
     }
 
-    /// Claim specified interface for exclusive access.
-    pub async fn claim_interface(&self, interface: u8) -> Result<()> {
-        JsFuture::from(self.dev().claim_interface(interface)).await?;
+    /// Releases all open interfaces and ends the device session, giving up
+    /// after the specified timeout.
+    ///
+    /// The device is closed in any case, but if the browser does not confirm this within
+    /// `timeout` an error of kind [`ErrorKind::TimedOut`] is returned. This prevents a
+    /// misbehaving device from blocking teardown indefinitely.
+    pub async fn close_timeout(self, timeout: Duration) -> Result<()> {
+        let close = JsFuture::from(self.state.start_close());
+        match future::select(close, pin!(time::sleep(timeout))).await {
+            Either::Left((res, _)) => {
+                res?;
+                Ok(())
+            }
+            Either::Right(((), _)) => Err(Error::new(ErrorKind::TimedOut, "closing USB device timed out")),
+        }
+    }
+
+    /// Resets the device and cancels all pending operations.
+    pub async fn reset(&self) -> Result<()> {
+        self.discard_carried_in(None);
+        let res = JsFuture::from(self.dev().reset()).await;
+        self.device.invalidate_cache();
+        res?;
         Ok(())
     }
 
+    /// Resets the device if supported by the browser and the device.
+    ///
+    /// Returns `Ok(true)` if the device was reset and `Ok(false)` if resetting is unsupported,
+    /// i.e. the browser does not provide the reset method or reports an error of
+    /// kind [`ErrorKind::Unsupported`]. Other failures are returned as errors.
+    /// This allows cleanup code to attempt a reset optionally.
+    pub async fn try_reset(&self) -> Result<bool> {
+        let has_reset = js_sys::Reflect::get(self.dev(), &"reset".into()).is_ok_and(|f| f.is_function());
+        if !has_reset {
+            return Ok(false);
+        }
+
+        match self.reset().await {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind == ErrorKind::Unsupported => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Selects the USB device configuration with the specified index.
+    ///
+    /// This releases all claimed interfaces.
+    pub async fn select_configuration(&self, configuration: u8) -> Result<()> {
+        self.discard_carried_in(None);
+        let res = JsFuture::from(self.dev().select_configuration(configuration)).await;
+        self.device.invalidate_cache();
+        res?;
+        self.claimed.borrow_mut().clear();
+        Ok(())
+    }
+
+    /// Claim specified interface for exclusive access.
+    pub async fn claim_interface(&self, interface: u8) -> Result<()> {
+        let res = JsFuture::from(self.dev().claim_interface(interface)).await;
+        self.device.invalidate_cache();
+        res?;
+        self.claimed.borrow_mut().insert(interface);
+        Ok(())
+    }
+
+    /// Claims all interfaces of the active configuration.
+    ///
+    /// Returns the numbers of the claimed interfaces. Claiming is all or nothing: if any
+    /// interface fails to be claimed, the interfaces claimed by this call are released again
+    /// and the error is returned. Interfaces that are already claimed are left untouched.
+    pub async fn claim_all_interfaces(&self) -> Result<Vec<u8>> {
+        let Some(cfg) = self.device.configuration() else {
+            return Err(Error::new(ErrorKind::InvalidAccess, "USB device is not configured"));
+        };
+
+        let mut claimed = Vec::new();
+        for iface in cfg.interfaces.iter().filter(|iface| !iface.claimed) {
+            if let Err(err) = self.claim_interface(iface.interface_number).await {
+                for &interface in &claimed {
+                    let _ = self.release_interface(interface).await;
+                }
+                return Err(err);
+            }
+            claimed.push(iface.interface_number);
+        }
+
+        Ok(cfg.interfaces.iter().map(|iface| iface.interface_number).collect())
+    }
+
     /// Release specified interface from exclusive access.
     pub async fn release_interface(&self, interface: u8) -> Result<()> {
-        JsFuture::from(self.dev().release_interface(interface)).await?;
+        self.discard_carried_in(Some(interface));
+        let res = JsFuture::from(self.dev().release_interface(interface)).await;
+        self.device.invalidate_cache();
+        res?;
+        self.claimed.borrow_mut().remove(&interface);
         Ok(())
     }
 
+    /// Claims the first interface of the active configuration with the specified class codes.
+    ///
+    /// The class codes are matched against the active alternate setting of each interface.
+    /// Subclass and protocol are only compared if specified. This avoids hardcoding interface
+    /// numbers in class drivers. The interface is released when the returned guard is dropped.
+    ///
+    /// An error of kind [`ErrorKind::InvalidAccess`] is returned if no interface matches.
+    pub async fn claim_interface_by_class(
+        &self, class: u8, subclass: Option<u8>, protocol: Option<u8>,
+    ) -> Result<InterfaceGuard<'_>> {
+        let Some(cfg) = self.device.configuration() else {
+            return Err(Error::new(ErrorKind::InvalidAccess, "USB device is not configured"));
+        };
+        let Some(iface) = cfg.interfaces.iter().find(|iface| {
+            let alt = &iface.alternate;
+            alt.interface_class == class
+                && subclass.is_none_or(|subclass| alt.interface_subclass == subclass)
+                && protocol.is_none_or(|protocol| alt.interface_protocol == protocol)
+        }) else {
+            return Err(Error::new(
+                ErrorKind::InvalidAccess,
+                format!("no interface of class {class:#04x} found"),
+            ));
+        };
+
+        self.claim_interface(iface.interface_number).await?;
+        Ok(InterfaceGuard { device: self, interface: iface.interface_number, released: false })
+    }
+
+    /// Claims the specified interface and returns a handle scoped to it.
+    ///
+    /// The handle only allows transfers on the endpoints of the active alternate setting of the
+    /// interface. This allows different components to own different interfaces of a composite
+    /// device. Multiple handles for different interfaces can coexist.
+    /// The interface is released when the handle is dropped.
+    pub async fn interface_handle(&self, interface: u8) -> Result<InterfaceHandle<'_>> {
+        self.claim_interface(interface).await?;
+        Ok(InterfaceHandle { guard: InterfaceGuard { device: self, interface, released: false } })
+    }
+
+    /// Numbers of the interfaces claimed through this handle in ascending order.
+    pub fn claimed_interfaces(&self) -> Vec<u8> {
+        self.claimed.borrow().iter().copied().collect()
+    }
+
     /// Selects the alternate setting with the specified index for an interface.
     pub async fn select_alternate_interface(&self, interface: u8, alternate: u8) -> Result<()> {
-        JsFuture::from(self.dev().select_alternate_interface(interface, alternate)).await?;
+        self.discard_carried_in(Some(interface));
+        let res = JsFuture::from(self.dev().select_alternate_interface(interface, alternate)).await;
+        self.device.invalidate_cache();
+        res?;
         Ok(())
     }
 
+    /// Selects the alternate setting with the specified index for an interface after
+    /// verifying that both exist in the active configuration.
+    ///
+    /// An error of kind [`ErrorKind::InvalidAccess`] listing the valid alternate settings
+    /// is returned if the interface or alternate setting does not exist.
+    pub async fn select_alternate_interface_checked(&self, interface: u8, alternate: u8) -> Result<()> {
+        let Some(cfg) = self.device.configuration() else {
+            return Err(Error::new(ErrorKind::InvalidAccess, "USB device is not configured"));
+        };
+        let Some(iface) = cfg.interfaces.iter().find(|iface| iface.interface_number == interface) else {
+            return Err(Error::new(
+                ErrorKind::InvalidAccess,
+                format!("interface {interface} does not exist in configuration {}", cfg.configuration_value),
+            ));
+        };
+        if !iface.alternates.iter().any(|alt| alt.alternate_setting == alternate) {
+            let valid = iface.alternates.iter().map(|alt| alt.alternate_setting.to_string()).collect::<Vec<_>>();
+            return Err(Error::new(
+                ErrorKind::InvalidAccess,
+                format!(
+                    "alternate setting {alternate} does not exist for interface {interface}, valid are: {}",
+                    valid.join(", ")
+                ),
+            ));
+        }
+
+        self.select_alternate_interface(interface, alternate).await
+    }
+
+    /// Endpoints of the active alternate setting of the specified interface.
+    ///
+    /// These are read from the configuration reported by the browser at the time of the call,
+    /// thus they reflect the alternate setting selected using
+    /// [`select_alternate_interface`](Self::select_alternate_interface), whereas a previously
+    /// obtained [`UsbInterface`] may be stale.
+    ///
+    /// An error of kind [`ErrorKind::InvalidAccess`] is returned if the device is not configured
+    /// or the interface does not exist.
+    pub fn endpoints(&self, interface: u8) -> Result<Vec<UsbEndpoint>> {
+        let Some(cfg) = self.dev().configuration() else {
+            return Err(Error::new(ErrorKind::InvalidAccess, "USB device is not configured"));
+        };
+        let Some(iface) = cfg
+            .interfaces()
+            .iter()
+            .filter_map(|iface| iface.dyn_into::<web_sys::UsbInterface>().ok())
+            .find(|iface| iface.interface_number() == interface)
+        else {
+            return Err(Error::new(ErrorKind::InvalidAccess, format!("interface {interface} does not exist")));
+        };
+        Ok(UsbAlternateInterface::from(&iface.alternate()).endpoints)
+    }
+
+    /// Queries the active alternate setting of an interface from the device.
+    ///
+    /// This issues a standard `GET_INTERFACE` request, thus unlike [`UsbInterface::alternate`]
+    /// the result reflects the state of the device rather than the state known to the browser.
+    pub async fn get_interface(&self, interface: u8) -> Result<u8> {
+        /// Standard `GET_INTERFACE` request.
+        const GET_INTERFACE: u8 = 0x0a;
+
+        let request = UsbControlRequest::new(
+            UsbRequestType::Standard,
+            UsbRecipient::Interface,
+            GET_INTERFACE,
+            0,
+            interface.into(),
+        );
+        match self.control_transfer_in(&request, 1).await?[..] {
+            [alternate] => Ok(alternate),
+            _ => Err(Error::new(ErrorKind::InvalidData, "USB device sent invalid GET_INTERFACE response")),
+        }
+    }
+
+    /// Queries whether the specified endpoint is halted.
+    ///
+    /// This issues a standard `GET_STATUS` request to the endpoint and checks its halt bit.
+    /// It allows avoiding unnecessary calls to [`clear_halt`](Self::clear_halt).
+    pub async fn is_endpoint_halted(&self, endpoint: u8, direction: UsbDirection) -> Result<bool> {
+        /// Standard `GET_STATUS` request.
+        const GET_STATUS: u8 = 0x00;
+
+        let address = match direction {
+            UsbDirection::In => endpoint | 0x80,
+            UsbDirection::Out => endpoint,
+        };
+        let request = UsbControlRequest::new(
+            UsbRequestType::Standard,
+            UsbRecipient::Endpoint,
+            GET_STATUS,
+            0,
+            address.into(),
+        );
+        match self.control_transfer_in(&request, 2).await?[..] {
+            [lo, _] => Ok(lo & 0x01 != 0),
+            _ => Err(Error::new(ErrorKind::InvalidData, "USB device sent invalid GET_STATUS response")),
+        }
+    }
+
+    /// Reads the current USB frame number as reported by the device.
+    ///
+    /// WebUSB does not provide access to the frame number of the host controller. Devices
+    /// that track start-of-frame packets can report it using a vendor-specific request instead,
+    /// which is issued as `control_request`. The device must respond with the frame number as
+    /// a little endian value of 2 or 4 bytes, otherwise an error of kind [`ErrorKind::InvalidData`]
+    /// is returned.
+    pub async fn frame_number(&self, control_request: &UsbControlRequest) -> Result<u32> {
+        match self.control_transfer_in(control_request, 4).await?[..] {
+            [lo, hi] => Ok(u16::from_le_bytes([lo, hi]).into()),
+            [a, b, c, d] => Ok(u32::from_le_bytes([a, b, c, d])),
+            _ => Err(Error::new(ErrorKind::InvalidData, "USB device sent invalid frame number")),
+        }
+    }
+
     /// Clears a halt condition.
     ///
     /// A halt condition is when a data transfer to or from the device has a status of 'stall',
@@ -942,6 +2989,137 @@ impl OpenUsbDevice {
         Ok(())
     }
 
+    /// Clears a halt condition after verifying that the endpoint belongs to the specified interface.
+    ///
+    /// The endpoint must belong to the active alternate setting of the interface within the
+    /// active configuration, otherwise an error of kind [`ErrorKind::InvalidAccess`] is returned
+    /// without clearing the halt. This catches mistakes such as using the wrong endpoint number.
+    pub async fn clear_halt_on(&self, interface: u8, direction: UsbDirection, endpoint: u8) -> Result<()> {
+        self.check_endpoint_owner(interface, direction, endpoint)?;
+        self.clear_halt(direction, endpoint).await
+    }
+
+    /// Recovers a stuck IN endpoint by clearing its halt condition and discarding stale data.
+    ///
+    /// After clearing the halt, transfers of `max_packet` bytes are performed until no data
+    /// arrives within `timeout`. Returns the total number of bytes discarded.
+    ///
+    /// Since WebUSB cannot cancel a transfer, the last transfer remains pending after
+    /// this method returns. It is not discarded: the next IN transfer on the endpoint,
+    /// such as [`transfer_in`](Self::transfer_in), receives its result instead of starting a new
+    /// transfer, thus no data sent by the device after the flush is lost. Data exceeding the
+    /// length requested by that transfer is returned by the following transfers.
+    pub async fn flush_in(&self, endpoint: u8, max_packet: u32, timeout: Duration) -> Result<usize> {
+        self.verify_claimed(UsbDirection::In, endpoint)?;
+        self.clear_halt(UsbDirection::In, endpoint).await?;
+
+        let mut discarded = 0;
+        while let Some(data) = self.raw_transfer_in_timeout(endpoint, max_packet, timeout).await? {
+            discarded += data.byte_length();
+        }
+
+        Ok(discarded)
+    }
+
+    /// Discards IN data and pending transfers carried over to the next transfer, since the
+    /// browser aborts transfers when the interface or, if `None`, the whole device is reconfigured.
+    fn discard_carried_in(&self, interface: Option<u8>) {
+        let mut carried = self.carried_in.borrow_mut();
+        match interface {
+            Some(interface) => {
+                for (iface, ep) in self.device.active_endpoints() {
+                    if iface == interface && ep.direction == UsbDirection::In {
+                        carried.remove(&ep.endpoint_number);
+                    }
+                }
+            }
+            None => carried.clear(),
+        }
+    }
+
+    /// Reads exactly `len` bytes from the specified bulk or interrupt endpoint,
+    /// accumulating the data of as many transfers as necessary.
+    ///
+    /// If the data has not been received completely by `deadline`, an error of kind
+    /// [`ErrorKind::TimedOut`] is returned. Since WebUSB cannot cancel a transfer,
+    /// the last transfer remains pending in this case. The data received so far and the
+    /// pending transfer are carried over to the next IN transfer on the endpoint, thus
+    /// a retry continues where the timed out read stopped.
+    pub async fn read_exact_deadline(&self, endpoint: u8, len: usize, deadline: Deadline) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(len);
+        while data.len() < len {
+            let transfer_len = Self::checked_len(len - data.len())?;
+            let remaining = deadline.remaining();
+            let received = match remaining.is_zero() {
+                true => None,
+                false => self.raw_transfer_in_timeout(endpoint, transfer_len, remaining).await?,
+            };
+
+            let Some(received) = received else {
+                if !data.is_empty() {
+                    let partial = Uint8Array::from(data.as_slice());
+                    let partial = js_sys::DataView::new(&partial.buffer(), 0, data.len());
+                    self.carry_in(endpoint, CarriedIn::Surplus(partial));
+                }
+                return Err(Error::new(ErrorKind::TimedOut, "read did not complete before deadline"));
+            };
+            data.extend(data_view_to_vec(&received));
+        }
+        Ok(data)
+    }
+
+    /// Reads a message that the device splits across multiple packets from the specified
+    /// bulk or interrupt endpoint.
+    ///
+    /// Packets of up to `packet_size` bytes are read and concatenated until `is_last` returns
+    /// `true` for a packet, which is the case for protocols marking all but the last packet of
+    /// a message with a continuation flag. The packets are returned unmodified, including any
+    /// per-packet headers.
+    pub async fn read_reassembled(
+        &self, endpoint: u8, packet_size: u32, is_last: impl Fn(&[u8]) -> bool,
+    ) -> Result<Vec<u8>> {
+        let mut message = Vec::new();
+        loop {
+            let packet = self.transfer_in(endpoint, packet_size).await?;
+            message.extend_from_slice(&packet);
+            if is_last(&packet) {
+                return Ok(message);
+            }
+        }
+    }
+
+    /// Converts a JavaScript exception raised by a transfer into an error.
+    ///
+    /// Security errors during transfers usually indicate that the endpoint belongs to an
+    /// interface the browser does not allow access to.
+    fn transfer_error(value: JsValue) -> Error {
+        let mut err = Error::from(value);
+        if err.kind == ErrorKind::Security {
+            err.msg.push_str(
+                " (the interface may not be claimed or may belong to a protected class, \
+                 such as HID or mass storage, that the browser blocks)",
+            );
+        }
+        err
+    }
+
+    /// Sets a hook that is invoked after each bulk, interrupt or control transfer completes.
+    ///
+    /// The hook receives a [`TransferLog`] describing the transfer and its result, which allows
+    /// collecting custom metrics without a logging framework. It is invoked synchronously on the
+    /// local thread from within the transfer method. A previously set hook is replaced.
+    pub fn set_transfer_hook(&self, hook: impl Fn(&TransferLog) + 'static) {
+        *self.transfer_hook.borrow_mut() = Some(Rc::new(hook));
+    }
+
+    /// Invokes the transfer hook, if any.
+    fn report_transfer(&self, direction: UsbDirection, endpoint: u8, length: u32, result: Result<u32>) {
+        let hook = self.transfer_hook.borrow().clone();
+        if let Some(hook) = hook {
+            hook(&TransferLog { direction, endpoint, length, result });
+        }
+    }
+
     /// Check transfer status.
     fn check_status(status: web_sys::UsbTransferStatus) -> Result<()> {
         match status {
@@ -950,48 +3128,261 @@ impl OpenUsbDevice {
             web_sys::UsbTransferStatus::Babble => {
                 Err(Error::new(ErrorKind::Babble, "USB device sent too much data"))
             }
-            other => unreachable!("unsupported UsbTransferStatus {other:?}"),
+            other => Err(Error::unexpected("transfer status", &JsValue::from_str(&format!("{other:?}")))),
         }
     }
 
     /// Perform a control transfer from device to host.
     pub async fn control_transfer_in(&self, control_request: &UsbControlRequest, len: u16) -> Result<Vec<u8>> {
-        let setup = web_sys::UsbControlTransferParameters::from(control_request);
-        let res = JsFuture::from(self.dev().control_transfer_in(&setup, len)).await?;
-        let res = res.dyn_into::<web_sys::UsbInTransferResult>().unwrap();
+        Ok(self.control_transfer_in_detailed(control_request, len).await?.data)
+    }
 
-        Self::check_status(res.status())?;
+    /// Perform a control transfer from device to host with the length specified as `usize`.
+    ///
+    /// An error of kind [`ErrorKind::InvalidAccess`] is returned if `len` exceeds [`u16::MAX`].
+    pub async fn control_transfer_in_usize(
+        &self, control_request: &UsbControlRequest, len: usize,
+    ) -> Result<Vec<u8>> {
+        self.control_transfer_in(control_request, Self::checked_len(len)?).await
+    }
 
-        let data = Uint8Array::new(&res.data().unwrap().buffer()).to_vec();
-        Ok(data)
+    /// Converts a transfer length to the type expected by WebUSB, failing on overflow.
+    fn checked_len<T: TryFrom<usize>>(len: usize) -> Result<T> {
+        T::try_from(len).map_err(|_| Error::new(ErrorKind::InvalidAccess, "transfer length is too large"))
+    }
+
+    /// Perform a control transfer from device to host and return details about the received data.
+    pub async fn control_transfer_in_detailed(
+        &self, control_request: &UsbControlRequest, len: u16,
+    ) -> Result<InTransferResult> {
+        let data = self.raw_control_transfer_in(control_request, len).await?;
+        Ok(InTransferResult::new(&data, len.into()))
+    }
+
+    /// Perform a control transfer from device to host into the provided buffer,
+    /// reusing its allocation.
+    ///
+    /// The buffer is cleared and filled with the received data. The number of bytes requested
+    /// from the device is `buf.capacity()`, limited to [`u16::MAX`], thus the buffer must be
+    /// created with sufficient capacity, for example using [`Vec::with_capacity`].
+    /// Returns the number of bytes received.
+    pub async fn control_transfer_in_into(
+        &self, control_request: &UsbControlRequest, buf: &mut Vec<u8>,
+    ) -> Result<usize> {
+        let len = buf.capacity().min(u16::MAX.into()) as u16;
+        let data = self.raw_control_transfer_in(control_request, len).await?;
+        let data = Uint8Array::new_with_byte_offset_and_length(
+            &data.buffer(),
+            data.byte_offset() as u32,
+            data.byte_length() as u32,
+        );
+
+        buf.clear();
+        buf.resize(data.length() as usize, 0);
+        data.copy_to(buf);
+        Ok(buf.len())
+    }
+
+    /// Performs a control transfer from the device and reports it to the transfer hook.
+    async fn raw_control_transfer_in(
+        &self, control_request: &UsbControlRequest, len: u16,
+    ) -> Result<js_sys::DataView> {
+        let setup = web_sys::UsbControlTransferParameters::from(control_request);
+        let _control = self.control.lock().await;
+        let res = async {
+            let res = JsFuture::from(self.dev().control_transfer_in(&setup, len))
+                .await
+                .map_err(Self::transfer_error)?;
+            let res = cast::<web_sys::UsbInTransferResult>(res, "transfer result")?;
+            Self::check_status(res.status())?;
+            present(res.data(), "transfer data")
+        }
+        .await;
+        self.report_transfer(UsbDirection::In, 0, len.into(), Self::received_len(&res));
+        res
+    }
+
+    /// Number of bytes received by a transfer from the device.
+    fn received_len(res: &Result<js_sys::DataView>) -> Result<u32> {
+        res.as_ref().map(|data| data.byte_length() as u32).map_err(Clone::clone)
     }
 
     /// Perform a control transfer from host to device.
     pub async fn control_transfer_out(&self, control_request: &UsbControlRequest, data: &[u8]) -> Result<u32> {
+        self.throttle_out(data.len()).await;
         let setup = web_sys::UsbControlTransferParameters::from(control_request);
+        let length = data.len() as u32;
         let data = Uint8Array::from(data);
-        let res = JsFuture::from(self.dev().control_transfer_out_with_u8_array(&setup, &data)?).await?;
-        let res = res.dyn_into::<web_sys::UsbOutTransferResult>().unwrap();
+        let _control = self.control.lock().await;
+        let res = async {
+            let res = JsFuture::from(self.dev().control_transfer_out_with_u8_array(&setup, &data)?)
+                .await
+                .map_err(Self::transfer_error)?;
+            let res = cast::<web_sys::UsbOutTransferResult>(res, "transfer result")?;
+            Self::check_status(res.status())?;
+            Ok(res.bytes_written())
+        }
+        .await;
+        self.report_transfer(UsbDirection::Out, 0, length, res.clone());
+        res
+    }
 
-        Self::check_status(res.status())?;
-        Ok(res.bytes_written())
+    /// Performs a control transfer from device to host and reads the received data as
+    /// a plain-old-data structure.
+    ///
+    /// Exactly `size_of::<T>()` bytes are requested. An error of kind [`ErrorKind::InvalidData`]
+    /// is returned if the device sends a different amount of data.
+    #[cfg(feature = "bytemuck")]
+    pub async fn control_read_pod<T: bytemuck::Pod>(&self, control_request: &UsbControlRequest) -> Result<T> {
+        let len = std::mem::size_of::<T>();
+        let data = self.control_transfer_in(control_request, Self::checked_len(len)?).await?;
+        if data.len() != len {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("USB device sent {} bytes, but {len} bytes were expected", data.len()),
+            ));
+        }
+        Ok(bytemuck::pod_read_unaligned(&data))
+    }
+
+    /// Reads a descriptor using a standard `GET_DESCRIPTOR` request.
+    ///
+    /// This reads the raw descriptor of any type `desc_type`, for example a HID or
+    /// class-specific descriptor, and underlies the specialized descriptor readers.
+    /// `index` selects the descriptor of the type, `lang_id` is the language of string
+    /// descriptors and zero otherwise. At most `len` bytes are returned.
+    pub async fn get_descriptor(&self, desc_type: u8, index: u8, lang_id: u16, len: u16) -> Result<Vec<u8>> {
+        let request = UsbControlRequest::new(
+            UsbRequestType::Standard,
+            UsbRecipient::Device,
+            descriptor::GET_DESCRIPTOR,
+            u16::from(desc_type) << 8 | u16::from(index),
+            lang_id,
+        );
+        self.control_transfer_in(&request, len).await
+    }
+
+    /// Reads the string descriptors with the specified indices in the language `lang_id`.
+    ///
+    /// This resolves many names, for example of all configurations and interfaces, in one call.
+    /// The descriptors are read one after another. For each index the decoded string is returned,
+    /// or `None` if the index is zero or reading or decoding the descriptor failed.
+    /// An error is only returned if the device is disconnected.
+    pub async fn string_descriptors(&self, indices: &[u8], lang_id: u16) -> Result<Vec<Option<String>>> {
+        let mut strings = Vec::with_capacity(indices.len());
+        for &index in indices {
+            let string = match index {
+                0 => None,
+                _ => match self.get_descriptor(descriptor::STRING, index, lang_id, 255).await {
+                    Ok(raw) => descriptor::parse_string(&raw),
+                    Err(err) if err.kind == ErrorKind::Disconnected => return Err(err),
+                    Err(_) => None,
+                },
+            };
+            strings.push(string);
+        }
+        Ok(strings)
+    }
+
+    /// Reads the raw configuration descriptor of the active configuration.
+    ///
+    /// The returned data starts with the configuration descriptor and contains all
+    /// interface, endpoint and class-specific descriptors of the configuration.
+    pub async fn raw_configuration_descriptor(&self) -> Result<Vec<u8>> {
+        let Some(active) = self.dev().configuration() else {
+            return Err(Error::new(ErrorKind::InvalidAccess, "USB device is not configured"));
+        };
+        let Some(index) = self
+            .device
+            .configurations()
+            .iter()
+            .position(|cfg| cfg.configuration_value == active.configuration_value())
+        else {
+            return Err(Error::new(ErrorKind::Other, "active configuration not found"));
+        };
+
+        let header = self.get_descriptor(descriptor::CONFIGURATION, index as u8, 0, 9).await?;
+        let [_, _, lo, hi, ..] = header[..] else {
+            return Err(Error::new(ErrorKind::InvalidData, "configuration descriptor is too short"));
+        };
+
+        self.get_descriptor(descriptor::CONFIGURATION, index as u8, 0, u16::from_le_bytes([lo, hi])).await
+    }
+
+    /// The active configuration including the fields only available from
+    /// the raw configuration descriptor.
+    ///
+    /// This reads the raw configuration descriptor from the device and merges it
+    /// with the configuration reported by the browser.
+    pub async fn configuration_from_descriptor(&self) -> Result<UsbConfiguration> {
+        let raw = UsbConfiguration::from_raw_descriptor(&self.raw_configuration_descriptor().await?)?;
+        let Some(mut cfg) = self.device.configuration() else {
+            return Err(Error::new(ErrorKind::InvalidAccess, "USB device is not configured"));
+        };
+        cfg.merge_raw(&raw);
+        Ok(cfg)
+    }
+
+    /// Reads and parses the Binary device Object Store (BOS) descriptor.
+    ///
+    /// Devices not supporting USB 2.1 or later usually do not provide a BOS descriptor
+    /// and stall the request.
+    pub async fn bos_descriptor(&self) -> Result<bos::BosDescriptor> {
+        let header = self.get_descriptor(descriptor::BOS, 0, 0, 5).await?;
+        let [_, _, lo, hi, ..] = header[..] else {
+            return Err(Error::new(ErrorKind::InvalidData, "BOS descriptor is too short"));
+        };
+
+        let raw = self.get_descriptor(descriptor::BOS, 0, 0, u16::from_le_bytes([lo, hi])).await?;
+        bos::BosDescriptor::parse(&raw)
+    }
+
+    /// Reads the Microsoft OS 2.0 descriptor set.
+    ///
+    /// The descriptor set is located using the Microsoft OS 2.0 platform capability of the
+    /// BOS descriptor and read using the vendor request announced there.
+    /// Returns `None` if the device provides no BOS descriptor or no such platform capability.
+    pub async fn ms_os_20_descriptor(&self) -> Result<Option<Vec<u8>>> {
+        /// `wIndex` of the request for retrieving the descriptor set.
+        const MS_OS_20_DESCRIPTOR_INDEX: u16 = 0x07;
+
+        let bos = match self.bos_descriptor().await {
+            Ok(bos) => bos,
+            Err(err) if err.kind() == ErrorKind::Stall => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let Some(info) = bos.ms_os_20_descriptor_set_info() else { return Ok(None) };
+
+        let request = UsbControlRequest::new(
+            UsbRequestType::Vendor,
+            UsbRecipient::Device,
+            info.vendor_code,
+            0,
+            MS_OS_20_DESCRIPTOR_INDEX,
+        );
+        Ok(Some(self.control_transfer_in(&request, info.total_length).await?))
     }
 
     /// Transmits time sensitive information from the device.
+    ///
+    /// The error of a failed packet states the index of the packet within the transfer.
     pub async fn isochronous_transfer_in(
         &self, endpoint: u8, packet_lens: impl IntoIterator<Item = u32>,
-    ) -> Result<Vec<Result<Vec<u8>>>> {
+    ) -> Result<Vec<IsochronousInPacket>> {
+        self.verify_claimed(UsbDirection::In, endpoint)?;
         let packet_lens = packet_lens.into_iter().map(|len| js_sys::Number::from(len as f64)).collect::<Vec<_>>();
 
-        let res = JsFuture::from(self.dev().isochronous_transfer_in(endpoint, &packet_lens)).await?;
-        let res = res.dyn_into::<web_sys::UsbIsochronousInTransferResult>().unwrap();
+        let res = JsFuture::from(self.dev().isochronous_transfer_in(endpoint, &packet_lens))
+            .await
+            .map_err(Self::transfer_error)?;
+        let res = cast::<web_sys::UsbIsochronousInTransferResult>(res, "transfer result")?;
 
         let mut results = Vec::new();
-        for packet in res.packets() {
-            let packet = packet.dyn_into::<web_sys::UsbIsochronousInTransferPacket>().unwrap();
+        for (index, packet) in res.packets().into_iter().enumerate() {
+            let packet = cast::<web_sys::UsbIsochronousInTransferPacket>(packet, "transfer packet")?;
             let result = match Self::check_status(packet.status()) {
-                Ok(()) => Ok(Uint8Array::new(&res.data().unwrap().buffer()).to_vec()),
-                Err(err) => Err(err),
+                Ok(()) => Ok(data_view_to_vec(&present(packet.data(), "transfer data")?)),
+                Err(err) => Err(Self::packet_error(index, err)),
             };
             results.push(result);
         }
@@ -999,12 +3390,40 @@ impl OpenUsbDevice {
         Ok(results)
     }
 
+    /// Continuously performs isochronous transfers from the device.
+    ///
+    /// Each item contains the packets of one transfer with the specified packet lengths.
+    /// A new transfer is issued when the stream is polled after the previous one completed.
+    /// Streaming stops when the stream is dropped; data received by an outstanding transfer
+    /// at that time is lost.
+    ///
+    /// Errors of individual packets are reported within an item. If a transfer fails as a whole,
+    /// for example because the device has been disconnected, the error is yielded and the
+    /// stream ends.
+    pub fn isochronous_in_stream(
+        &self, endpoint: u8, packet_lens: Vec<u32>,
+    ) -> impl Stream<Item = Result<Vec<IsochronousInPacket>>> + '_ {
+        stream::unfold(false, move |failed| {
+            let packet_lens = packet_lens.clone();
+            async move {
+                if failed {
+                    return None;
+                }
+                let res = self.isochronous_transfer_in(endpoint, packet_lens).await;
+                let failed = res.is_err();
+                Some((res, failed))
+            }
+        })
+    }
+
     /// Transmits time sensitive information to the device.
     ///
     /// Returns the number of bytes sent of each packet.
+    /// The error of a failed packet states the index of the packet within the transfer.
     pub async fn isochronous_transfer_out(
         &self, endpoint: u8, packets: impl IntoIterator<Item = &[u8]>,
     ) -> Result<Vec<Result<u32>>> {
+        self.verify_claimed(UsbDirection::Out, endpoint)?;
         let mut data = Vec::new();
         let mut lens = Vec::new();
 
@@ -1016,16 +3435,17 @@ impl OpenUsbDevice {
         let data = Uint8Array::from(&data[..]);
         let lens = lens.into_iter().map(|len| js_sys::Number::from(len as f64)).collect::<Vec<_>>();
 
-        let res =
-            JsFuture::from(self.dev().isochronous_transfer_out_with_u8_array(endpoint, &data, &lens)?).await?;
-        let res = res.dyn_into::<web_sys::UsbIsochronousOutTransferResult>().unwrap();
+        let res = JsFuture::from(self.dev().isochronous_transfer_out_with_u8_array(endpoint, &data, &lens)?)
+            .await
+            .map_err(Self::transfer_error)?;
+        let res = cast::<web_sys::UsbIsochronousOutTransferResult>(res, "transfer result")?;
 
         let mut results = Vec::new();
-        for packet in res.packets() {
-            let packet = packet.dyn_into::<web_sys::UsbIsochronousOutTransferPacket>().unwrap();
+        for (index, packet) in res.packets().into_iter().enumerate() {
+            let packet = cast::<web_sys::UsbIsochronousOutTransferPacket>(packet, "transfer packet")?;
             let result = match Self::check_status(packet.status()) {
                 Ok(()) => Ok(packet.bytes_written()),
-                Err(err) => Err(err),
+                Err(err) => Err(Self::packet_error(index, err)),
             };
             results.push(result);
         }
@@ -1033,39 +3453,829 @@ impl OpenUsbDevice {
         Ok(results)
     }
 
+    /// Adds the index of the failed packet of an isochronous transfer to an error.
+    fn packet_error(index: usize, err: Error) -> Error {
+        Error::new(err.kind, format!("isochronous packet {index}: {}", err.msg))
+    }
+
     /// Performs a bulk or interrupt transfer from specified endpoint of the device.
     pub async fn transfer_in(&self, endpoint: u8, len: u32) -> Result<Vec<u8>> {
-        let res = JsFuture::from(self.dev().transfer_in(endpoint, len)).await?;
-        let res = res.dyn_into::<web_sys::UsbInTransferResult>().unwrap();
+        Ok(self.transfer_in_detailed(endpoint, len).await?.data)
+    }
+
+    /// Performs a bulk or interrupt transfer from specified endpoint of the device,
+    /// giving up after the specified timeout.
+    ///
+    /// Returns `Ok(None)` if no data arrived within `timeout`, which distinguishes the absence
+    /// of data from an error. This is useful for polling interrupt endpoints that only
+    /// send data occasionally. Since WebUSB cannot cancel a transfer, it remains pending
+    /// after a timeout and the next IN transfer on the endpoint receives its result, thus
+    /// no data is lost when polling in a loop.
+    pub async fn transfer_in_or_timeout(
+        &self, endpoint: u8, len: u32, timeout: Duration,
+    ) -> Result<Option<Vec<u8>>> {
+        let data = self.raw_transfer_in_timeout(endpoint, len, timeout).await?;
+        Ok(data.map(|data| data_view_to_vec(&data)))
+    }
 
-        Self::check_status(res.status())?;
+    /// Performs a bulk or interrupt transfer from specified endpoint of the device
+    /// and measures its duration.
+    ///
+    /// The elapsed time is measured using the monotonic clock `performance.now()` and
+    /// includes the time waiting for the device to send data. This allows building latency
+    /// statistics without instrumenting each call.
+    pub async fn transfer_in_timed(&self, endpoint: u8, len: u32) -> Result<(Vec<u8>, Duration)> {
+        let start = time::now_ms();
+        let data = self.transfer_in(endpoint, len).await?;
+        Ok((data, time::elapsed_since(start)))
+    }
 
-        let data = Uint8Array::new(&res.data().unwrap().buffer()).to_vec();
-        Ok(data)
+    /// Performs a bulk or interrupt transfer from specified endpoint of the device after
+    /// verifying that the endpoint belongs to the specified interface.
+    ///
+    /// The endpoint must be an IN endpoint of the active alternate setting of the interface
+    /// within the active configuration, otherwise an error of kind [`ErrorKind::InvalidAccess`]
+    /// is returned without performing a transfer.
+    pub async fn transfer_in_on(&self, interface: u8, endpoint: u8, len: u32) -> Result<Vec<u8>> {
+        self.check_endpoint_owner(interface, UsbDirection::In, endpoint)?;
+        self.transfer_in(endpoint, len).await
+    }
+
+    /// Enables or disables checking that an endpoint's interface is claimed before each transfer.
+    ///
+    /// Transferring on an endpoint of an unclaimed interface fails with an error of the browser
+    /// that does not state the cause. When enabled, bulk, interrupt and isochronous transfers
+    /// instead fail with an error of kind [`ErrorKind::InvalidAccess`] naming the interface
+    /// if it has not been claimed through this handle, see
+    /// [`claimed_interfaces`](Self::claimed_interfaces).
+    /// This is intended for development and disabled by default, since it looks up the
+    /// endpoint in the active configuration for each transfer.
+    pub fn set_claim_check(&self, enabled: bool) {
+        self.claim_check.set(enabled);
+    }
+
+    /// Verifies that the interface owning the endpoint is claimed, if enabled by
+    /// [`set_claim_check`](Self::set_claim_check).
+    fn verify_claimed(&self, direction: UsbDirection, endpoint: u8) -> Result<()> {
+        if !self.claim_check.get() {
+            return Ok(());
+        }
+
+        let owner = self
+            .device
+            .active_endpoints()
+            .into_iter()
+            .find(|(_, ep)| ep.endpoint_number == endpoint && ep.direction == direction)
+            .map(|(interface, _)| interface);
+        match owner {
+            Some(interface) if self.claimed.borrow().contains(&interface) => Ok(()),
+            Some(interface) => {
+                Err(Error::new(ErrorKind::InvalidAccess, format!("interface {interface} not claimed")))
+            }
+            None => Err(Error::new(
+                ErrorKind::InvalidAccess,
+                format!("{direction:?} endpoint {endpoint} does not belong to any interface"),
+            )),
+        }
+    }
+
+    /// Verifies that the endpoint belongs to the active alternate setting of the interface.
+    fn check_endpoint_owner(&self, interface: u8, direction: UsbDirection, endpoint: u8) -> Result<()> {
+        let owned = self.device.configuration().is_some_and(|cfg| {
+            cfg.interfaces.iter().filter(|iface| iface.interface_number == interface).any(|iface| {
+                iface
+                    .alternate
+                    .endpoints
+                    .iter()
+                    .any(|ep| ep.endpoint_number == endpoint && ep.direction == direction)
+            })
+        });
+        if !owned {
+            return Err(Error::new(
+                ErrorKind::InvalidAccess,
+                format!("{direction:?} endpoint {endpoint} does not belong to interface {interface}"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Recommended maximum length of a single transfer of the specified type.
+    ///
+    /// The browser does not expose its transfer size limits, which depend on the browser,
+    /// the operating system and its USB stack. Thus these are heuristic constants chosen
+    /// conservatively to work on all common platforms, for example considering the 16 MiB
+    /// memory limit shared by all pending transfers on Linux. Larger transfers may work on
+    /// some platforms. Chunking helpers can use this value to size their transfers.
+    ///
+    /// | Type        | Limit   |
+    /// |-------------|---------|
+    /// | Bulk        | 1 MiB   |
+    /// | Interrupt   | 64 kiB  |
+    /// | Isochronous | 64 kiB  |
+    pub fn recommended_max_transfer(&self, endpoint_type: UsbEndpointType) -> u32 {
+        match endpoint_type {
+            UsbEndpointType::Bulk => 1 << 20,
+            UsbEndpointType::Interrupt | UsbEndpointType::Isochronous => 1 << 16,
+        }
+    }
+
+    /// Limits the rate at which data is sent to the device.
+    ///
+    /// Subsequent calls to [`transfer_out`](Self::transfer_out) and
+    /// [`control_transfer_out`](Self::control_transfer_out) are delayed using a timer,
+    /// so that on average no more than `bytes_per_sec` bytes are sent per second.
+    /// This prevents overruns of devices with slow firmware. The time at which the next
+    /// transfer may start is accounted using the monotonic clock `performance.now()`.
+    ///
+    /// A limit of zero disables rate limiting.
+    pub fn set_out_rate_limit(&self, bytes_per_sec: u32) {
+        self.out_rate_limit.set((bytes_per_sec > 0).then_some(bytes_per_sec));
+        self.out_next_ms.set(0.);
+    }
+
+    /// Waits until sending `len` bytes stays within the configured rate limit.
+    async fn throttle_out(&self, len: usize) {
+        // The budget is reserved only once the wait is over, so that a cancelled transfer
+        // does not delay subsequent ones.
+        loop {
+            let Some(bytes_per_sec) = self.out_rate_limit.get() else { return };
+
+            let now = time::now_ms();
+            let start = self.out_next_ms.get();
+            if start <= now {
+                self.out_next_ms.set(now + len as f64 * 1000. / f64::from(bytes_per_sec));
+                return;
+            }
+            time::sleep(Duration::from_secs_f64((start - now) / 1000.)).await;
+        }
+    }
+
+    /// Sets the length used by [`transfer_in_default`](Self::transfer_in_default)
+    /// for this device session.
+    ///
+    /// This centralizes tuning of the read length, for example to read multiple packets
+    /// per transfer. It takes precedence over the maximum packet size of the endpoint,
+    /// which is used when no default read length is set.
+    pub fn set_default_read_len(&self, len: u32) {
+        self.default_read_len.set(Some(len));
+    }
+
+    /// Performs a bulk or interrupt transfer from specified endpoint of the device
+    /// using the default read length.
+    ///
+    /// The length set by [`set_default_read_len`](Self::set_default_read_len) is requested.
+    /// If none is set, the [maximum packet size](Self::max_packet_size) of the endpoint is used.
+    /// An error of kind [`ErrorKind::InvalidAccess`] is returned if neither is available.
+    pub async fn transfer_in_default(&self, endpoint: u8) -> Result<Vec<u8>> {
+        let Some(len) = self.default_read_len.get().or_else(|| self.max_packet_size(endpoint, UsbDirection::In))
+        else {
+            return Err(Error::new(
+                ErrorKind::InvalidAccess,
+                format!("no read length known for endpoint {endpoint}"),
+            ));
+        };
+        self.transfer_in(endpoint, len).await
+    }
+
+    /// Performs a bulk or interrupt transfer from specified endpoint of the device
+    /// with the length specified as `usize`.
+    ///
+    /// An error of kind [`ErrorKind::InvalidAccess`] is returned if `len` exceeds [`u32::MAX`].
+    pub async fn transfer_in_usize(&self, endpoint: u8, len: usize) -> Result<Vec<u8>> {
+        self.transfer_in(endpoint, Self::checked_len(len)?).await
+    }
+
+    /// Performs a bulk or interrupt transfer from the endpoint with the specified address.
+    ///
+    /// The address is the full `bEndpointAddress`, for example `0x81`, including the direction bit.
+    /// An error of kind [`ErrorKind::InvalidAccess`] is returned if it designates an OUT endpoint.
+    pub async fn transfer_in_addr(&self, address: u8, len: u32) -> Result<Vec<u8>> {
+        self.transfer_in(Self::endpoint_number(address, UsbDirection::In)?, len).await
+    }
+
+    /// Extracts the endpoint number from an endpoint address after verifying its direction bit.
+    fn endpoint_number(address: u8, direction: UsbDirection) -> Result<u8> {
+        let address_direction = if address & 0x80 != 0 { UsbDirection::In } else { UsbDirection::Out };
+        if address_direction != direction {
+            return Err(Error::new(
+                ErrorKind::InvalidAccess,
+                format!("endpoint address {address:#04x} does not match transfer direction {direction:?}"),
+            ));
+        }
+        Ok(address & 0x0f)
+    }
+
+    /// Performs a bulk or interrupt transfer from specified endpoint of the device
+    /// and returns details about the received data.
+    pub async fn transfer_in_detailed(&self, endpoint: u8, len: u32) -> Result<InTransferResult> {
+        let data = self.raw_transfer_in(endpoint, len).await?;
+        Ok(InTransferResult::new(&data, len))
+    }
+
+    /// Performs a bulk or interrupt transfer from the device and reports it to the transfer hook.
+    async fn raw_transfer_in(&self, endpoint: u8, len: u32) -> Result<js_sys::DataView> {
+        self.verify_claimed(UsbDirection::In, endpoint)?;
+        let carried = self.start_transfer_in(endpoint, len);
+        self.complete_transfer_in(endpoint, len, carried).await
+    }
+
+    /// Performs a transfer from the device like [`raw_transfer_in`](Self::raw_transfer_in),
+    /// giving up after `timeout`.
+    ///
+    /// On timeout `None` is returned and the transfer is carried over to the next IN transfer
+    /// on the endpoint, thus data the device sends later is not lost.
+    async fn raw_transfer_in_timeout(
+        &self, endpoint: u8, len: u32, timeout: Duration,
+    ) -> Result<Option<js_sys::DataView>> {
+        self.verify_claimed(UsbDirection::In, endpoint)?;
+        let carried = self.start_transfer_in(endpoint, len);
+        let CarriedIn::Pending(transfer, transfer_len) = &carried else {
+            return self.complete_transfer_in(endpoint, len, carried).await.map(Some);
+        };
+        let pending = CarriedIn::Pending(transfer.clone(), *transfer_len);
+
+        let completion = pin!(self.complete_transfer_in(endpoint, len, carried));
+        match future::select(completion, pin!(time::sleep(timeout))).await {
+            Either::Left((res, _)) => res.map(Some),
+            Either::Right(((), _)) => {
+                self.carry_in(endpoint, pending);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Takes the data carried over on the specified endpoint or starts a new transfer.
+    fn start_transfer_in(&self, endpoint: u8, len: u32) -> CarriedIn {
+        let carried = self.carried_in.borrow_mut().get_mut(&endpoint).and_then(|queue| queue.pop_front());
+        match carried {
+            Some(CarriedIn::Surplus(data)) => CarriedIn::Surplus(self.split_surplus(endpoint, len, data)),
+            Some(pending) => pending,
+            None => CarriedIn::Pending(self.dev().transfer_in(endpoint, len), len),
+        }
+    }
+
+    /// Waits for data obtained by [`start_transfer_in`](Self::start_transfer_in)
+    /// and reports it to the transfer hook.
+    async fn complete_transfer_in(&self, endpoint: u8, len: u32, carried: CarriedIn) -> Result<js_sys::DataView> {
+        let res = match carried {
+            CarriedIn::Surplus(data) => Ok(data),
+            CarriedIn::Pending(transfer, _) => async {
+                let res = JsFuture::from(transfer).await.map_err(Self::transfer_error)?;
+                let res = cast::<web_sys::UsbInTransferResult>(res, "transfer result")?;
+                Self::check_status(res.status())?;
+                present(res.data(), "transfer data")
+            }
+            .await
+            .map(|data| self.split_surplus(endpoint, len, data)),
+        };
+        self.report_transfer(UsbDirection::In, endpoint, len, Self::received_len(&res));
+        res
+    }
+
+    /// Returns the first `len` bytes of the data and carries the rest over to the next
+    /// IN transfer on the endpoint.
+    ///
+    /// A transfer carried over from a timed out read may have requested more data than
+    /// the reader it is passed to.
+    fn split_surplus(&self, endpoint: u8, len: u32, data: js_sys::DataView) -> js_sys::DataView {
+        let len = len as usize;
+        if data.byte_length() <= len {
+            return data;
+        }
+
+        let buffer = data.buffer();
+        let rest = js_sys::DataView::new(&buffer, data.byte_offset() + len, data.byte_length() - len);
+        self.carry_in(endpoint, CarriedIn::Surplus(rest));
+        js_sys::DataView::new(&buffer, data.byte_offset(), len)
+    }
+
+    /// Carries data or a pending transfer over to the next IN transfer on the endpoint.
+    fn carry_in(&self, endpoint: u8, carried: CarriedIn) {
+        self.carried_in.borrow_mut().entry(endpoint).or_default().push_front(carried);
+    }
+
+    /// Performs a bulk or interrupt transfer from specified endpoint of the device
+    /// and returns the received data as a JavaScript [`DataView`](js_sys::DataView).
+    ///
+    /// No copy of the data into Rust memory is made, allowing binary structures to be
+    /// parsed in place using the accessor methods of the `DataView`, for example
+    /// [`get_uint32_endian`](js_sys::DataView::get_uint32_endian).
+    ///
+    /// The `DataView` references a buffer on the JavaScript heap, which is kept alive
+    /// for as long as the `DataView` is.
+    pub async fn transfer_in_data_view(&self, endpoint: u8, len: u32) -> Result<js_sys::DataView> {
+        self.raw_transfer_in(endpoint, len).await
+    }
+
+    /// Performs a bulk or interrupt transfer from specified endpoint of the device
+    /// directly into the provided buffer.
+    ///
+    /// At most `buf.len()` bytes are requested from the device and no heap allocation
+    /// is performed on the Rust side. Returns the number of bytes received.
+    pub async fn transfer_in_copy_to(&self, endpoint: u8, buf: &mut [u8]) -> Result<usize> {
+        let data = self.raw_transfer_in(endpoint, buf.len() as u32).await?;
+        let data = Uint8Array::new_with_byte_offset_and_length(
+            &data.buffer(),
+            data.byte_offset() as u32,
+            data.byte_length() as u32,
+        );
+        let len = data.length() as usize;
+        if len > buf.len() {
+            return Err(Error::new(ErrorKind::Babble, "USB device sent too much data"));
+        }
+
+        data.copy_to(&mut buf[..len]);
+        Ok(len)
+    }
+
+    /// Continuously reads from the specified bulk or interrupt endpoint while keeping
+    /// multiple transfers in flight.
+    ///
+    /// Up to `depth` transfers of `len` bytes each are submitted concurrently. This hides
+    /// the latency between completion of a transfer and submission of the next one and
+    /// thus improves throughput on high-bandwidth endpoints.
+    ///
+    /// Results are yielded in submission order, which is the order the data was sent by
+    /// the device. New transfers are only submitted while the stream is polled, thus a slow
+    /// consumer provides backpressure and never has more than `depth` transfers outstanding.
+    /// Data received by outstanding transfers when the stream is dropped is lost.
+    pub fn pipelined_reader(
+        &self, endpoint: u8, len: u32, depth: usize,
+    ) -> impl Stream<Item = Result<Vec<u8>>> + '_ {
+        stream::repeat_with(move || self.transfer_in(endpoint, len)).buffered(depth.max(1))
+    }
+
+    /// Continuously reads `len` bytes per transfer from the specified bulk or interrupt
+    /// endpoint while measuring the throughput.
+    ///
+    /// The returned [`ThroughputHandle`] reports the amount of data received by the stream
+    /// and the resulting rate. It is updated as the stream is polled, thus it reflects the
+    /// rate of the consumer when the stream is polled slower than the device sends data.
+    pub fn metered_reader(
+        &self, endpoint: u8, len: u32,
+    ) -> (impl Stream<Item = Result<Vec<u8>>> + '_, ThroughputHandle) {
+        let handle = ThroughputHandle::default();
+        let meter = handle.clone();
+        let reader =
+            stream::repeat_with(move || self.transfer_in(endpoint, len)).then(|fut| fut).inspect(move |res| {
+                if let Ok(data) = res {
+                    meter.record(data.len());
+                }
+            });
+        (reader, handle)
+    }
+
+    /// Builds a map for looking up endpoints by interface, direction and transfer type.
+    ///
+    /// The map is a snapshot of the active alternate settings of all interfaces of the
+    /// active configuration and must be rebuilt after selecting another configuration
+    /// or alternate setting. See [`EndpointMap`] for details.
+    pub fn build_endpoint_map(&self) -> EndpointMap {
+        let mut endpoints = BTreeMap::new();
+        for (interface, ep) in self.device.active_endpoints() {
+            endpoints.entry((interface, ep.direction, ep.endpoint_type)).or_insert(ep.endpoint_number);
+        }
+        EndpointMap { endpoints }
+    }
+
+    /// Returns a helper for request/response protocols using the specified endpoint pair.
+    ///
+    /// Each request sends data to `out_endpoint` and then reads a response of up to
+    /// `read_len` bytes from `in_endpoint`. See [`Duplex`] for details.
+    pub fn duplex(&self, in_endpoint: u8, out_endpoint: u8, read_len: u32) -> Duplex<'_> {
+        Duplex { device: self, in_endpoint, out_endpoint, read_len, lock: Mutex::new(()) }
+    }
+
+    /// Continuously reads frames of a vendor-specific protocol from the specified
+    /// bulk or interrupt endpoint.
+    ///
+    /// The frame format is described by `config`, see the [`frame`] module for details.
+    /// Frames are assembled across transfers and each complete frame, including its header,
+    /// is yielded. If a magic prefix is configured, data preceding it is skipped and
+    /// a header specifying a frame larger than the maximum frame size causes
+    /// resynchronization on the next magic prefix.
+    ///
+    /// Transfers are performed using [`transfer_in_default`](Self::transfer_in_default),
+    /// thus their length can be tuned using [`set_default_read_len`](Self::set_default_read_len).
+    /// An invalid frame format results in an error of kind [`ErrorKind::InvalidAccess`].
+    /// Data of an incomplete frame is lost when the stream is dropped.
+    pub fn read_framed(
+        &self, endpoint: u8, config: frame::FrameConfig,
+    ) -> impl Stream<Item = Result<Vec<u8>>> + '_ {
+        stream::unfold(Some(frame::FrameParser::new(config)), move |parser| async move {
+            let mut parser = match parser? {
+                Ok(parser) => parser,
+                Err(err) => return Some((Err(err), None)),
+            };
+            loop {
+                if let Some(frame) = parser.next_frame() {
+                    return Some((Ok(frame), Some(Ok(parser))));
+                }
+                match self.transfer_in_default(endpoint).await {
+                    Ok(data) => parser.push(&data),
+                    Err(err) => return Some((Err(err), Some(Ok(parser)))),
+                }
+            }
+        })
     }
 
     /// Performs a bulk or interrupt transfer to the specified endpoint of the device.
     ///
     /// Returns the number of bytes sent.
     pub async fn transfer_out(&self, endpoint: u8, data: &[u8]) -> Result<u32> {
+        self.verify_claimed(UsbDirection::Out, endpoint)?;
+        self.throttle_out(data.len()).await;
+        let length = data.len() as u32;
         let data = Uint8Array::from(data);
-        let res = JsFuture::from(self.dev().transfer_out_with_u8_array(endpoint, &data)?).await?;
-        let res = res.dyn_into::<web_sys::UsbOutTransferResult>().unwrap();
+        let res = async {
+            let res = JsFuture::from(self.dev().transfer_out_with_u8_array(endpoint, &data)?)
+                .await
+                .map_err(Self::transfer_error)?;
+            let res = cast::<web_sys::UsbOutTransferResult>(res, "transfer result")?;
+            Self::check_status(res.status())?;
+            Ok(res.bytes_written())
+        }
+        .await;
+        self.report_transfer(UsbDirection::Out, endpoint, length, res.clone());
+        res
+    }
 
-        Self::check_status(res.status())?;
+    /// Sends a zero-length packet to the specified endpoint.
+    ///
+    /// The device considers a transfer complete when it receives a packet shorter than the
+    /// maximum packet size of the endpoint. If the length of the transferred data is an exact
+    /// multiple of the packet size, protocols relying on this require a zero-length packet to
+    /// terminate the transfer. [`transfer_out_with_zlp`](Self::transfer_out_with_zlp) sends it
+    /// automatically when needed.
+    pub async fn send_zlp(&self, endpoint: u8) -> Result<()> {
+        self.transfer_out(endpoint, &[]).await?;
+        Ok(())
+    }
+
+    /// Performs a bulk or interrupt transfer to the specified endpoint of the device
+    /// and terminates it with a zero-length packet if necessary.
+    ///
+    /// A zero-length packet is sent after the data if its length is an exact multiple
+    /// of the packet size of the endpoint, see [`send_zlp`](Self::send_zlp).
+    /// The endpoint must belong to a claimed interface, otherwise an error of kind
+    /// [`ErrorKind::InvalidAccess`] is returned before sending data.
+    ///
+    /// Returns the number of bytes sent.
+    pub async fn transfer_out_with_zlp(&self, endpoint: u8, data: &[u8]) -> Result<u32> {
+        let Some(packet_size) = self.max_packet_size(endpoint, UsbDirection::Out) else {
+            return Err(Error::new(
+                ErrorKind::InvalidAccess,
+                format!("endpoint {endpoint} does not belong to a claimed interface"),
+            ));
+        };
+
+        let written = self.transfer_out(endpoint, data).await?;
+        if !data.is_empty() && data.len().is_multiple_of(packet_size as usize) {
+            self.send_zlp(endpoint).await?;
+        }
+        Ok(written)
+    }
+
+    /// Performs a bulk or interrupt transfer to the specified endpoint of the device
+    /// and measures its duration.
+    ///
+    /// Returns the number of bytes sent and the elapsed time measured using the
+    /// monotonic clock `performance.now()`.
+    pub async fn transfer_out_timed(&self, endpoint: u8, data: &[u8]) -> Result<(u32, Duration)> {
+        let start = time::now_ms();
+        let written = self.transfer_out(endpoint, data).await?;
+        Ok((written, time::elapsed_since(start)))
+    }
 
-        Ok(res.bytes_written())
+    /// Performs a bulk or interrupt transfer to the endpoint with the specified address.
+    ///
+    /// The address is the full `bEndpointAddress`, for example `0x02`, including the direction bit.
+    /// An error of kind [`ErrorKind::InvalidAccess`] is returned if it designates an IN endpoint.
+    ///
+    /// Returns the number of bytes sent.
+    pub async fn transfer_out_addr(&self, address: u8, data: &[u8]) -> Result<u32> {
+        self.transfer_out(Self::endpoint_number(address, UsbDirection::Out)?, data).await
     }
 }
 
-impl Drop for OpenUsbDevice {
+/// An interface claimed using [`OpenUsbDevice::claim_interface_by_class`].
+///
+/// Dropping this releases the interface.
+#[derive(Debug)]
+pub struct InterfaceGuard<'a> {
+    device: &'a OpenUsbDevice,
+    interface: u8,
+    released: bool,
+}
+
+impl InterfaceGuard<'_> {
+    /// The number of the claimed interface.
+    pub fn interface(&self) -> u8 {
+        self.interface
+    }
+
+    /// The opened device the interface belongs to.
+    pub fn device(&self) -> &OpenUsbDevice {
+        self.device
+    }
+
+    /// Releases the interface.
+    ///
+    /// It is not necessary to call this method, since dropping the guard
+    /// also releases the interface, but this allows errors to be handled.
+    pub async fn release(mut self) -> Result<()> {
+        self.released = true;
+        self.device.release_interface(self.interface).await
+    }
+}
+
+impl Drop for InterfaceGuard<'_> {
     fn drop(&mut self) {
-        if !self.closed {
-            let device = self.dev().clone();
-            let fut = JsFuture::from(device.close());
+        if !self.released {
+            self.device.claimed.borrow_mut().remove(&self.interface);
+            self.device.discard_carried_in(Some(self.interface));
+            self.device.device.invalidate_cache();
+
+            let fut = JsFuture::from(self.device.dev().release_interface(self.interface));
             spawn_local(async move {
                 let _ = fut.await;
             });
         }
     }
 }
+
+/// Request/response helper for a pair of bulk or interrupt endpoints,
+/// obtained by [`OpenUsbDevice::duplex`].
+///
+/// Requests issued through the same helper are serialized: a request sends its data and
+/// reads the response before the next request starts, even if requests are issued concurrently.
+/// Concurrent requests are queued and performed in the order they were issued.
+/// Thus each response is returned to the request that caused it.
+#[derive(Debug)]
+pub struct Duplex<'a> {
+    device: &'a OpenUsbDevice,
+    in_endpoint: u8,
+    out_endpoint: u8,
+    read_len: u32,
+    lock: Mutex<()>,
+}
+
+impl Duplex<'_> {
+    /// Sends `data` and returns the response.
+    pub async fn request(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let _lock = self.lock.lock().await;
+        self.device.transfer_out(self.out_endpoint, data).await?;
+        self.device.transfer_in(self.in_endpoint, self.read_len).await
+    }
+}
+
+/// Request/response helper for protocols that multiplex requests over a pair of bulk
+/// or interrupt endpoints using correlation identifiers.
+///
+/// Unlike [`Duplex`], multiple requests may be outstanding at the same time and their
+/// responses may arrive in any order. A read loop running in the background reads responses
+/// from the IN endpoint and passes each to the request whose identifier is extracted from it.
+/// Responses without an identifier or without a matching outstanding request are discarded.
+///
+/// If reading fails, all outstanding and further requests fail with that error.
+/// Dropping the multiplexer stops the read loop.
+#[derive(Debug)]
+pub struct Multiplexer {
+    device: Rc<OpenUsbDevice>,
+    out_endpoint: u8,
+    state: Rc<RefCell<MultiplexerState>>,
+    _stop: oneshot::Sender<()>,
+}
+
+/// State shared between a [`Multiplexer`] and its read loop.
+#[derive(Debug, Default)]
+struct MultiplexerState {
+    pending: BTreeMap<u64, oneshot::Sender<Result<Vec<u8>>>>,
+    failed: Option<Error>,
+}
+
+impl Multiplexer {
+    /// Starts multiplexing requests over the specified endpoint pair of the device.
+    ///
+    /// Responses are read using transfers of `read_len` bytes from `in_endpoint`.
+    /// `id_of` extracts the correlation identifier from a response, returning `None`
+    /// if the response does not carry one.
+    pub fn new(
+        device: Rc<OpenUsbDevice>, in_endpoint: u8, out_endpoint: u8, read_len: u32,
+        id_of: impl Fn(&[u8]) -> Option<u64> + 'static,
+    ) -> Self {
+        let state: Rc<RefCell<MultiplexerState>> = Default::default();
+        let (stop_tx, stop_rx) = oneshot::channel();
+
+        let reader = device.clone();
+        let loop_state = state.clone();
+        spawn_local(async move {
+            let read_loop = async {
+                loop {
+                    match reader.transfer_in(in_endpoint, read_len).await {
+                        Ok(data) => {
+                            let Some(id) = id_of(&data) else { continue };
+                            if let Some(tx) = loop_state.borrow_mut().pending.remove(&id) {
+                                let _ = tx.send(Ok(data));
+                            }
+                        }
+                        Err(err) => {
+                            let mut state = loop_state.borrow_mut();
+                            for (_, tx) in mem::take(&mut state.pending) {
+                                let _ = tx.send(Err(err.clone()));
+                            }
+                            state.failed = Some(err);
+                            break;
+                        }
+                    }
+                }
+            };
+            future::select(pin!(read_loop), stop_rx).await;
+        });
+
+        Self { device, out_endpoint, state, _stop: stop_tx }
+    }
+
+    /// Sends `data` as the request with identifier `id` and returns its response.
+    ///
+    /// An error of kind [`ErrorKind::InvalidAccess`] is returned if a request with
+    /// the same identifier is already outstanding.
+    pub async fn request(&self, id: u64, data: &[u8]) -> Result<Vec<u8>> {
+        let rx = {
+            let mut state = self.state.borrow_mut();
+            if let Some(err) = &state.failed {
+                return Err(err.clone());
+            }
+            if state.pending.get(&id).is_some_and(|tx| !tx.is_closed()) {
+                return Err(Error::new(ErrorKind::InvalidAccess, format!("request {id} is already outstanding")));
+            }
+            let (tx, rx) = oneshot::channel();
+            state.pending.insert(id, tx);
+            rx
+        };
+
+        if let Err(err) = self.device.transfer_out(self.out_endpoint, data).await {
+            self.state.borrow_mut().pending.remove(&id);
+            return Err(err);
+        }
+
+        match rx.await {
+            Ok(res) => res,
+            Err(_) => Err(Error::new(ErrorKind::Other, "multiplexer read loop ended")),
+        }
+    }
+}
+
+/// Endpoints of a device by interface, direction and transfer type,
+/// obtained by [`OpenUsbDevice::build_endpoint_map`].
+///
+/// This allows resolving endpoints once in driver code instead of searching the
+/// configuration for each transfer. If an interface has multiple endpoints of the
+/// same direction and transfer type, the first one is used.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EndpointMap {
+    endpoints: BTreeMap<(u8, UsbDirection, UsbEndpointType), u8>,
+}
+
+impl EndpointMap {
+    /// Number of the endpoint of the interface with the specified direction and transfer type.
+    pub fn get(&self, interface: u8, direction: UsbDirection, endpoint_type: UsbEndpointType) -> Option<u8> {
+        self.endpoints.get(&(interface, direction, endpoint_type)).copied()
+    }
+
+    /// Number of the bulk IN endpoint of the interface.
+    pub fn bulk_in(&self, interface: u8) -> Option<u8> {
+        self.get(interface, UsbDirection::In, UsbEndpointType::Bulk)
+    }
+
+    /// Number of the bulk OUT endpoint of the interface.
+    pub fn bulk_out(&self, interface: u8) -> Option<u8> {
+        self.get(interface, UsbDirection::Out, UsbEndpointType::Bulk)
+    }
+
+    /// Number of the interrupt IN endpoint of the interface.
+    pub fn interrupt_in(&self, interface: u8) -> Option<u8> {
+        self.get(interface, UsbDirection::In, UsbEndpointType::Interrupt)
+    }
+
+    /// Number of the interrupt OUT endpoint of the interface.
+    pub fn interrupt_out(&self, interface: u8) -> Option<u8> {
+        self.get(interface, UsbDirection::Out, UsbEndpointType::Interrupt)
+    }
+
+    /// Number of the isochronous IN endpoint of the interface.
+    pub fn isochronous_in(&self, interface: u8) -> Option<u8> {
+        self.get(interface, UsbDirection::In, UsbEndpointType::Isochronous)
+    }
+
+    /// Number of the isochronous OUT endpoint of the interface.
+    pub fn isochronous_out(&self, interface: u8) -> Option<u8> {
+        self.get(interface, UsbDirection::Out, UsbEndpointType::Isochronous)
+    }
+}
+
+/// Throughput of a stream obtained by [`OpenUsbDevice::metered_reader`].
+///
+/// Time is measured using the monotonic clock `performance.now()` from the moment
+/// the handle was created. Clones share the same measurement.
+#[derive(Debug, Clone)]
+pub struct ThroughputHandle {
+    meter: Rc<Cell<Meter>>,
+}
+
+/// Measurement state of a [`ThroughputHandle`].
+#[derive(Debug, Clone, Copy)]
+struct Meter {
+    start_ms: f64,
+    last_ms: f64,
+    bytes: u64,
+}
+
+impl Default for ThroughputHandle {
+    fn default() -> Self {
+        let now = time::now_ms();
+        Self { meter: Rc::new(Cell::new(Meter { start_ms: now, last_ms: now, bytes: 0 })) }
+    }
+}
+
+impl ThroughputHandle {
+    /// Records the reception of `len` bytes.
+    fn record(&self, len: usize) {
+        let mut meter = self.meter.get();
+        meter.bytes += len as u64;
+        meter.last_ms = time::now_ms();
+        self.meter.set(meter);
+    }
+
+    /// Total number of bytes received.
+    pub fn bytes(&self) -> u64 {
+        self.meter.get().bytes
+    }
+
+    /// Average rate in bytes per second from creation of the handle until the
+    /// most recently received data.
+    ///
+    /// This is zero until data has been received.
+    pub fn bytes_per_sec(&self) -> f64 {
+        let Meter { start_ms, last_ms, bytes } = self.meter.get();
+        if last_ms <= start_ms {
+            return 0.;
+        }
+        bytes as f64 * 1000. / (last_ms - start_ms)
+    }
+}
+
+/// A claimed interface of an opened device, obtained by [`OpenUsbDevice::interface_handle`].
+///
+/// Transfers are restricted to the endpoints of the active alternate setting of the interface;
+/// using another endpoint results in an error of kind [`ErrorKind::InvalidAccess`].
+///
+/// Dropping this releases the interface.
+#[derive(Debug)]
+pub struct InterfaceHandle<'a> {
+    guard: InterfaceGuard<'a>,
+}
+
+impl InterfaceHandle<'_> {
+    /// The number of the interface.
+    pub fn interface(&self) -> u8 {
+        self.guard.interface
+    }
+
+    /// Performs a bulk or interrupt transfer from the specified endpoint of the interface.
+    pub async fn transfer_in(&self, endpoint: u8, len: u32) -> Result<Vec<u8>> {
+        self.guard.device.check_endpoint_owner(self.guard.interface, UsbDirection::In, endpoint)?;
+        self.guard.device.transfer_in(endpoint, len).await
+    }
+
+    /// Performs a bulk or interrupt transfer to the specified endpoint of the interface.
+    ///
+    /// Returns the number of bytes sent.
+    pub async fn transfer_out(&self, endpoint: u8, data: &[u8]) -> Result<u32> {
+        self.guard.device.check_endpoint_owner(self.guard.interface, UsbDirection::Out, endpoint)?;
+        self.guard.device.transfer_out(endpoint, data).await
+    }
+
+    /// Clears a halt condition of the specified endpoint of the interface.
+    pub async fn clear_halt(&self, direction: UsbDirection, endpoint: u8) -> Result<()> {
+        self.guard.device.clear_halt_on(self.guard.interface, direction, endpoint).await
+    }
+
+    /// Releases the interface.
+    ///
+    /// It is not necessary to call this method, since dropping the handle
+    /// also releases the interface, but this allows errors to be handled.
+    pub async fn release(self) -> Result<()> {
+        self.guard.release().await
+    }
+}
+
+impl Drop for OpenUsbDevice {
+    fn drop(&mut self) {
+        self.device.invalidate_cache();
+
+        if !self.state.closed.get() {
+            let _ = self.state.start_close();
+        }
+    }
+}
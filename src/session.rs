@@ -0,0 +1,50 @@
+//! Tracking of device sessions opened through this crate.
+//!
+//! The browser only reports whether a device is open, but not whether a handle to it
+//! still exists in this page. This registry records the state of each session, so that
+//! [`UsbDevice::open`](crate::UsbDevice::open) can distinguish a handle that is still in use
+//! from a dropped handle whose device is still closing.
+
+use std::cell::RefCell;
+
+use js_sys::{Object, Promise};
+
+/// State of a device session.
+#[derive(Debug, Clone)]
+pub(crate) enum Session {
+    /// A handle to the open device exists.
+    Open,
+    /// The device was left open without a handle.
+    Detached,
+    /// The handle was dropped and the device is closing, resolving the promise when done.
+    Closing(Promise),
+}
+
+thread_local! {
+    static SESSIONS: RefCell<Vec<(web_sys::UsbDevice, Session)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// State of the session of the device, if any.
+pub(crate) fn get(device: &web_sys::UsbDevice) -> Option<Session> {
+    SESSIONS.with_borrow(|sessions| sessions.iter().find(|(dev, _)| dev == device).map(|(_, s)| s.clone()))
+}
+
+/// Sets the state of the session of the device.
+pub(crate) fn set(device: &web_sys::UsbDevice, session: Session) {
+    remove(device);
+    SESSIONS.with_borrow_mut(|sessions| sessions.push((device.clone(), session)));
+}
+
+/// Removes the session of the device.
+pub(crate) fn remove(device: &web_sys::UsbDevice) {
+    SESSIONS.with_borrow_mut(|sessions| sessions.retain(|(dev, _)| dev != device));
+}
+
+/// Removes the session of the device if it is still closing using the specified promise.
+pub(crate) fn remove_closed(device: &web_sys::UsbDevice, closing: &Promise) {
+    SESSIONS.with_borrow_mut(|sessions| {
+        sessions.retain(|(dev, session)| {
+            !(dev == device && matches!(session, Session::Closing(p) if Object::is(p, closing)))
+        })
+    });
+}
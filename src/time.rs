@@ -0,0 +1,65 @@
+//! Timers using the JavaScript event loop.
+
+use std::time::Duration;
+
+use js_sys::{Function, Promise, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+/// Waits for the specified duration.
+pub(crate) async fn sleep(duration: Duration) {
+    let promise = Promise::new(&mut |resolve, _reject| {
+        let set_timeout = Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))
+            .expect("setTimeout is unavailable")
+            .unchecked_into::<Function>();
+        set_timeout
+            .call2(&JsValue::UNDEFINED, &resolve, &JsValue::from_f64(duration.as_secs_f64() * 1000.))
+            .expect("setTimeout failed");
+    });
+    let _ = JsFuture::from(promise).await;
+}
+
+/// Current value of the monotonic clock in milliseconds.
+///
+/// Uses `performance.now()`, which is available in windows and workers,
+/// and falls back to the wall clock otherwise.
+pub(crate) fn now_ms() -> f64 {
+    let performance = Reflect::get(&js_sys::global(), &JsValue::from_str("performance")).ok();
+    performance
+        .and_then(|perf| {
+            let now = Reflect::get(&perf, &JsValue::from_str("now")).ok()?.dyn_into::<Function>().ok()?;
+            now.call0(&perf).ok()?.as_f64()
+        })
+        .unwrap_or_else(js_sys::Date::now)
+}
+
+/// Time elapsed since `start_ms`, a value previously returned by [`now_ms`].
+pub(crate) fn elapsed_since(start_ms: f64) -> Duration {
+    Duration::from_secs_f64(((now_ms() - start_ms) / 1000.).max(0.))
+}
+
+/// A point in time by which an operation must complete.
+///
+/// Since [`std::time::Instant`] is unavailable on `wasm32-unknown-unknown`, this is
+/// measured using the monotonic clock `performance.now()` of the JavaScript environment.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Deadline {
+    at_ms: f64,
+}
+
+impl Deadline {
+    /// Deadline the specified duration from now.
+    pub fn after(duration: Duration) -> Self {
+        Self { at_ms: now_ms() + duration.as_secs_f64() * 1000. }
+    }
+
+    /// Time remaining until the deadline, or zero if it has passed.
+    pub fn remaining(&self) -> Duration {
+        Duration::from_secs_f64(((self.at_ms - now_ms()) / 1000.).max(0.))
+    }
+
+    /// Whether the deadline has passed.
+    pub fn has_passed(&self) -> bool {
+        now_ms() >= self.at_ms
+    }
+}
@@ -0,0 +1,100 @@
+use wasm_bindgen_test::wasm_bindgen_test;
+
+mod mock;
+
+use webusb_web::{
+    bos::{BosCapability, BosDescriptor},
+    ErrorKind,
+};
+
+/// WebUSB platform capability UUID {3408b638-09a9-47a0-8bfd-a0768815b665}.
+const WEBUSB_UUID: [u8; 16] =
+    [0x38, 0xb6, 0x08, 0x34, 0xa9, 0x09, 0xa0, 0x47, 0x8b, 0xfd, 0xa0, 0x76, 0x88, 0x15, 0xb6, 0x65];
+
+/// BOS descriptor with a USB 2.0 extension and a WebUSB platform capability.
+const BOS: &[u8] = &[
+    0x05, 0x0f, 0x24, 0x00, 0x02, // BOS header
+    0x07, 0x10, 0x02, 0x06, 0x00, 0x00, 0x00, // USB 2.0 extension
+    0x18, 0x10, 0x05, 0x00, // platform capability
+    0x38, 0xb6, 0x08, 0x34, 0xa9, 0x09, 0xa0, 0x47, 0x8b, 0xfd, 0xa0, 0x76, 0x88, 0x15, 0xb6, 0x65, // UUID
+    0x00, 0x01, 0x01, 0x01, // bcdVersion, bVendorCode, iLandingPage
+];
+
+#[wasm_bindgen_test]
+fn platform_capability() {
+    let bos = BosDescriptor::parse(BOS).unwrap();
+    assert_eq!(bos.capabilities.len(), 2);
+
+    let ext = &bos.capabilities[0];
+    assert_eq!(ext.capability_type, BosCapability::USB_20_EXTENSION);
+    assert_eq!(ext.data, [0x06, 0x00, 0x00, 0x00]);
+    assert_eq!(ext.platform_uuid(), None);
+
+    let webusb = bos.platform_capability(&WEBUSB_UUID).unwrap();
+    assert_eq!(webusb.capability_type, BosCapability::PLATFORM);
+    assert_eq!(webusb.platform_data(), Some(&[0x00, 0x01, 0x01, 0x01][..]));
+}
+
+#[wasm_bindgen_test]
+fn invalid() {
+    assert_eq!(BosDescriptor::parse(&BOS[5..]).unwrap_err().kind(), ErrorKind::InvalidData);
+    assert_eq!(BosDescriptor::parse(&[]).unwrap_err().kind(), ErrorKind::InvalidData);
+}
+
+/// BOS descriptor with a Microsoft OS 2.0 platform capability for Windows 8.1.
+const MS_OS_20_BOS: &[u8] = &[
+    0x05, 0x0f, 0x21, 0x00, 0x01, // BOS header
+    0x1c, 0x10, 0x05, 0x00, // platform capability
+    0xdf, 0x60, 0xdd, 0xd8, 0x89, 0x45, 0xc7, 0x4c, 0x9c, 0xd2, 0x65, 0x9d, 0x9e, 0x64, 0x8a, 0x9f, // UUID
+    0x00, 0x00, 0x03, 0x06, 0x0a, 0x00, 0x21, 0x00, // descriptor set information
+];
+
+/// Microsoft OS 2.0 descriptor set consisting of the set header only.
+const MS_OS_20_SET: &[u8] = &[0x0a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x06, 0x0a, 0x00];
+
+#[wasm_bindgen_test]
+fn ms_os_20_descriptor_set_info() {
+    let info = BosDescriptor::parse(MS_OS_20_BOS).unwrap().ms_os_20_descriptor_set_info().unwrap();
+    assert_eq!(info.windows_version, 0x06030000);
+    assert_eq!(info.total_length, 10);
+    assert_eq!(info.vendor_code, 0x21);
+    assert_eq!(info.alt_enum_code, 0);
+
+    assert_eq!(BosDescriptor::parse(BOS).unwrap().ms_os_20_descriptor_set_info(), None);
+}
+
+/// Mock device returning the specified BOS descriptor and the Microsoft OS 2.0 descriptor set.
+fn ms_os_20_device(bos: Option<&[u8]>) -> webusb_web::UsbDevice {
+    mock::install_classes();
+    let bos = match bos {
+        Some(bos) => format!("{bos:?}"),
+        None => "null".to_string(),
+    };
+    mock::device(&format!(
+        "{{ {}
+            controlTransferIn(setup, len) {{
+                const reply = (data) => Promise.resolve(
+                    new USBInTransferResult('ok', new DataView(new Uint8Array(data.slice(0, len)).buffer)));
+                if (setup.requestType == 'standard' && setup.request == 6 && setup.value == 0x0f00) {{
+                    const bos = {bos};
+                    return bos ? reply(bos) : Promise.resolve(new USBInTransferResult('stall', undefined));
+                }}
+                if (setup.requestType == 'vendor' && setup.request == 0x21 && setup.index == 7) return reply({MS_OS_20_SET:?});
+                return Promise.resolve(new USBInTransferResult('stall', undefined));
+            }},
+        }}",
+        mock::OPEN_CLOSE
+    ))
+}
+
+#[wasm_bindgen_test]
+async fn ms_os_20_descriptor() {
+    let open = ms_os_20_device(Some(MS_OS_20_BOS)).open().await.unwrap();
+    assert_eq!(open.ms_os_20_descriptor().await.unwrap().as_deref(), Some(MS_OS_20_SET));
+
+    let open = ms_os_20_device(Some(BOS)).open().await.unwrap();
+    assert_eq!(open.ms_os_20_descriptor().await.unwrap(), None);
+
+    let open = ms_os_20_device(None).open().await.unwrap();
+    assert_eq!(open.ms_os_20_descriptor().await.unwrap(), None);
+}
@@ -0,0 +1,45 @@
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use webusb_web::cdc::{parse_cdc_functional_descriptors, CdcFunctionalDescriptor};
+
+/// Configuration descriptor of an STM32 virtual COM port (CDC-ACM).
+const CDC_ACM_CONFIG: &[u8] = &[
+    0x09, 0x02, 0x43, 0x00, 0x02, 0x01, 0x00, 0xc0, 0x32, // configuration
+    0x09, 0x04, 0x00, 0x00, 0x01, 0x02, 0x02, 0x01, 0x00, // communication interface
+    0x05, 0x24, 0x00, 0x10, 0x01, // header
+    0x05, 0x24, 0x01, 0x00, 0x01, // call management
+    0x04, 0x24, 0x02, 0x02, // abstract control management
+    0x05, 0x24, 0x06, 0x00, 0x01, // union
+    0x07, 0x05, 0x82, 0x03, 0x08, 0x00, 0x10, // notification endpoint
+    0x09, 0x04, 0x01, 0x00, 0x02, 0x0a, 0x00, 0x00, 0x00, // data interface
+    0x07, 0x05, 0x01, 0x02, 0x40, 0x00, 0x00, // bulk out endpoint
+    0x07, 0x05, 0x81, 0x02, 0x40, 0x00, 0x00, // bulk in endpoint
+];
+
+#[wasm_bindgen_test]
+fn cdc_acm() {
+    assert_eq!(CDC_ACM_CONFIG.len(), 0x43);
+
+    let descs = parse_cdc_functional_descriptors(CDC_ACM_CONFIG);
+    assert_eq!(
+        descs,
+        [
+            CdcFunctionalDescriptor::Header { bcd_cdc: 0x0110 },
+            CdcFunctionalDescriptor::CallManagement { capabilities: 0x00, data_interface: 1 },
+            CdcFunctionalDescriptor::AbstractControlManagement { capabilities: 0x02 },
+            CdcFunctionalDescriptor::Union { control_interface: 0, subordinate_interfaces: vec![1] },
+        ]
+    );
+}
+
+#[wasm_bindgen_test]
+fn truncated() {
+    let descs = parse_cdc_functional_descriptors(&CDC_ACM_CONFIG[..30]);
+    assert_eq!(
+        descs,
+        [
+            CdcFunctionalDescriptor::Header { bcd_cdc: 0x0110 },
+            CdcFunctionalDescriptor::CallManagement { capabilities: 0x00, data_interface: 1 },
+        ]
+    );
+}
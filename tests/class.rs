@@ -0,0 +1,53 @@
+use wasm_bindgen_test::wasm_bindgen_test;
+
+mod mock;
+
+use webusb_web::*;
+
+#[wasm_bindgen_test]
+fn protected_classes() {
+    for class in [0x01, 0x03, 0x08, 0x0b, 0x0e, 0x10, 0xe0] {
+        assert!(is_protected_class(class), "class {class:#x} must be protected");
+    }
+    for class in [0x00, 0x02, 0x07, 0x0a, 0xef, 0xfe, 0xff] {
+        assert!(!is_protected_class(class), "class {class:#x} must not be protected");
+    }
+}
+
+#[wasm_bindgen_test]
+fn filter_blocked() {
+    assert!(!UsbDeviceFilter::new().is_likely_blocked());
+    assert!(!UsbDeviceFilter::new().with_class_code(0xff).is_likely_blocked());
+    assert!(UsbDeviceFilter::new().with_class_code(0x03).is_likely_blocked());
+}
+
+#[wasm_bindgen_test]
+fn filter_matches() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ vendorId: 0x1234, productId: 1, deviceClass: 0, deviceSubclass: 0, deviceProtocol: 0, serialNumber: 'abc', {} }}",
+        mock::CONFIGURED
+    ));
+
+    assert!(UsbDeviceFilter::new().matches(&dev));
+    assert!(UsbDeviceFilter::new().with_vendor_id(0x1234).with_product_id(1).matches(&dev));
+    assert!(!UsbDeviceFilter::new().with_vendor_id(0x1234).with_product_id(2).matches(&dev));
+    assert!(UsbDeviceFilter::new().with_serial_number("abc").matches(&dev));
+    assert!(!UsbDeviceFilter::new().with_serial_number("def").matches(&dev));
+    assert!(UsbDeviceFilter::new().with_class_code(0x03).matches(&dev), "interface class");
+    assert!(!UsbDeviceFilter::new().with_class_code(0x03).with_subclass_code(1).matches(&dev));
+    assert!(!UsbDeviceFilter::new().with_class_code(0x08).matches(&dev));
+}
+
+#[wasm_bindgen_test]
+fn named_class_codes() {
+    assert_eq!(class_codes::VENDOR_SPECIFIC, 0xff);
+    assert_eq!(class_codes::CDC, 0x02);
+    assert_eq!(class_codes::HID, 0x03);
+    assert_eq!(class_codes::MASS_STORAGE, 0x08);
+    assert!(is_protected_class(class_codes::HID));
+    assert!(!is_protected_class(class_codes::VENDOR_SPECIFIC));
+
+    let filter = UsbDeviceFilter::new().with_class_code(class_codes::VENDOR_SPECIFIC);
+    assert!(!filter.is_likely_blocked());
+}
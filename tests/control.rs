@@ -0,0 +1,169 @@
+use wasm_bindgen_test::wasm_bindgen_test;
+
+mod mock;
+
+use webusb_web::*;
+
+/// Mock device that rejects overlapping control transfers like the browser does.
+const EXCLUSIVE_CONTROL: &str = "
+    busy: false,
+    controlTransferIn(setup, length) {
+        if (this.busy) {
+            return Promise.reject(new DOMException('A control transfer is in progress.', 'InvalidStateError'));
+        }
+        this.busy = true;
+        return new Promise(resolve => setTimeout(() => {
+            this.busy = false;
+            resolve(new USBInTransferResult('ok', new DataView(new Uint8Array([setup.request]).buffer)));
+        }, 10));
+    },
+";
+
+#[wasm_bindgen_test]
+async fn concurrent_control_transfers() {
+    mock::install_classes();
+    let dev = mock::device(&format!("{{ {} {EXCLUSIVE_CONTROL} }}", mock::OPEN_CLOSE));
+    let open = dev.open().await.unwrap();
+
+    let req1 = UsbControlRequest::new(UsbRequestType::Vendor, UsbRecipient::Device, 1, 0, 0);
+    let req2 = UsbControlRequest::new(UsbRequestType::Vendor, UsbRecipient::Device, 2, 0, 0);
+    let (res1, res2) = tokio::join!(open.control_transfer_in(&req1, 1), open.control_transfer_in(&req2, 1));
+
+    assert_eq!(res1.unwrap(), [1]);
+    assert_eq!(res2.unwrap(), [2]);
+}
+
+#[wasm_bindgen_test]
+fn from_setup_bytes() {
+    // GET_DESCRIPTOR for the device descriptor.
+    let (req, dir, len) =
+        UsbControlRequest::from_setup_bytes(&[0x80, 0x06, 0x00, 0x01, 0x00, 0x00, 0x12, 0x00]).unwrap();
+    assert_eq!(req, UsbControlRequest::new(UsbRequestType::Standard, UsbRecipient::Device, 0x06, 0x0100, 0));
+    assert_eq!(dir, UsbDirection::In);
+    assert_eq!(len, 0x12);
+
+    // Vendor request to an interface.
+    let (req, dir, len) =
+        UsbControlRequest::from_setup_bytes(&[0x41, 0x12, 0x34, 0x12, 0x02, 0x00, 0x00, 0x01]).unwrap();
+    assert_eq!(req, UsbControlRequest::new(UsbRequestType::Vendor, UsbRecipient::Interface, 0x12, 0x1234, 2));
+    assert_eq!(dir, UsbDirection::Out);
+    assert_eq!(len, 0x100);
+}
+
+#[wasm_bindgen_test]
+fn from_setup_bytes_reserved() {
+    let err = UsbControlRequest::from_setup_bytes(&[0x60, 0, 0, 0, 0, 0, 0, 0]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidAccess);
+
+    let err = UsbControlRequest::from_setup_bytes(&[0x04, 0, 0, 0, 0, 0, 0, 0]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidAccess);
+}
+
+#[wasm_bindgen_test]
+fn to_setup_bytes() {
+    let req = UsbControlRequest::new(UsbRequestType::Vendor, UsbRecipient::Interface, 0x12, 0x1234, 0x0502);
+    assert_eq!(req.to_setup_bytes(UsbDirection::In, 64), [0xc1, 0x12, 0x34, 0x12, 0x02, 0x05, 0x40, 0x00]);
+    assert_eq!(req.to_setup_bytes(UsbDirection::Out, 0), [0x41, 0x12, 0x34, 0x12, 0x02, 0x05, 0x00, 0x00]);
+
+    let bytes = req.to_setup_bytes(UsbDirection::In, 300);
+    assert_eq!(UsbControlRequest::from_setup_bytes(&bytes).unwrap(), (req, UsbDirection::In, 300));
+}
+
+#[wasm_bindgen_test]
+async fn string_descriptors() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            controlTransferIn(setup, len) {{
+                const strings = {{ 1: 'Vendor', 2: 'Gerät' }};
+                const text = strings[setup.value & 0xff];
+                if (setup.request != 6 || setup.value >> 8 != 3 || setup.index != 0x0409 || text === undefined) {{
+                    return Promise.resolve(new USBInTransferResult('stall', undefined));
+                }}
+                const data = [2 + 2 * text.length, 3];
+                for (const c of text) data.push(c.charCodeAt(0) & 0xff, c.charCodeAt(0) >> 8);
+                return Promise.resolve(new USBInTransferResult('ok', new DataView(new Uint8Array(data.slice(0, len)).buffer)));
+            }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let strings = open.string_descriptors(&[1, 0, 7, 2], 0x0409).await.unwrap();
+    assert_eq!(strings, [Some("Vendor".to_string()), None, None, Some("Gerät".to_string())]);
+}
+
+#[wasm_bindgen_test]
+async fn control_transfer_in_into() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            controlTransferIn(setup, len) {{
+                const data = new Uint8Array(Math.min(len, setup.value)).fill(setup.request);
+                return Promise.resolve(new USBInTransferResult('ok', new DataView(data.buffer)));
+            }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let mut buf = Vec::with_capacity(16);
+    let req = UsbControlRequest::new(UsbRequestType::Vendor, UsbRecipient::Device, 1, 4, 0);
+    assert_eq!(open.control_transfer_in_into(&req, &mut buf).await.unwrap(), 4);
+    assert_eq!(buf, [1; 4]);
+    let ptr = buf.as_ptr();
+
+    let req = UsbControlRequest::new(UsbRequestType::Vendor, UsbRecipient::Device, 2, 100, 0);
+    assert_eq!(open.control_transfer_in_into(&req, &mut buf).await.unwrap(), 16);
+    assert_eq!(buf, [2; 16]);
+    assert_eq!(buf.as_ptr(), ptr);
+}
+
+#[wasm_bindgen_test]
+async fn frame_number() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            controlTransferIn(setup, len) {{
+                const responses = {{ 1: [0x34, 0x07], 2: [0x78, 0x56, 0x34, 0x12], 3: [0x01] }};
+                const data = new Uint8Array(responses[setup.request].slice(0, len));
+                return Promise.resolve(new USBInTransferResult('ok', new DataView(data.buffer)));
+            }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let req = |request| UsbControlRequest::new(UsbRequestType::Vendor, UsbRecipient::Device, request, 0, 0);
+    assert_eq!(open.frame_number(&req(1)).await.unwrap(), 0x734);
+    assert_eq!(open.frame_number(&req(2)).await.unwrap(), 0x12345678);
+    assert_eq!(open.frame_number(&req(3)).await.unwrap_err().kind(), ErrorKind::InvalidData);
+}
+
+#[wasm_bindgen_test]
+async fn get_descriptor() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            controlTransferIn(setup, len) {{
+                if (setup.requestType != 'standard' || setup.request != 6 || setup.value != 0x0100) {{
+                    return Promise.resolve(new USBInTransferResult('stall', undefined));
+                }}
+                const data = [18, 1, 0x00, 0x02, 0, 0, 0, 64, 0x34, 0x12, 0xcd, 0xab, 0, 1, 1, 2, 3, 1];
+                return Promise.resolve(new USBInTransferResult('ok', new DataView(new Uint8Array(data.slice(0, len)).buffer)));
+            }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let desc = open.get_descriptor(0x01, 0, 0, 18).await.unwrap();
+    assert_eq!(desc.len(), 18);
+    assert_eq!(desc[1], 0x01);
+    assert_eq!(u16::from_le_bytes([desc[8], desc[9]]), 0x1234);
+
+    assert_eq!(open.get_descriptor(0x01, 0, 0, 8).await.unwrap().len(), 8);
+
+    let err = open.get_descriptor(0x21, 0, 0, 9).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Stall);
+}
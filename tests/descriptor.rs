@@ -0,0 +1,152 @@
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use webusb_web::*;
+
+/// Configuration descriptor of a SuperSpeed device with a bulk IN/OUT endpoint pair.
+const SUPERSPEED_CONFIG: &[u8] = &[
+    0x09, 0x02, 0x2c, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32, // configuration
+    0x09, 0x04, 0x00, 0x00, 0x02, 0xff, 0x00, 0x00, 0x00, // interface
+    0x07, 0x05, 0x81, 0x02, 0x00, 0x04, 0x00, // bulk in endpoint
+    0x06, 0x30, 0x0f, 0x00, 0x00, 0x00, // endpoint companion
+    0x07, 0x05, 0x02, 0x02, 0x00, 0x04, 0x00, // bulk out endpoint
+    0x06, 0x30, 0x03, 0x00, 0x00, 0x00, // endpoint companion
+];
+
+#[wasm_bindgen_test]
+fn superspeed_endpoint_companion() {
+    let cfg = UsbConfiguration::from_raw_descriptor(SUPERSPEED_CONFIG).unwrap();
+    assert_eq!(cfg.configuration_value, 1);
+    assert_eq!(cfg.interfaces.len(), 1);
+
+    let alt = &cfg.interfaces[0].alternate;
+    assert_eq!(alt.interface_class, 0xff);
+    assert_eq!(alt.endpoints.len(), 2);
+
+    let ep_in = &alt.endpoints[0];
+    assert_eq!((ep_in.endpoint_number, ep_in.direction), (1, UsbDirection::In));
+    assert_eq!(ep_in.endpoint_type, UsbEndpointType::Bulk);
+    assert_eq!(ep_in.packet_size, 1024);
+    assert_eq!(ep_in.max_burst, Some(15));
+    assert_eq!(ep_in.bytes_per_interval, Some(0));
+
+    let ep_out = &alt.endpoints[1];
+    assert_eq!((ep_out.endpoint_number, ep_out.direction), (2, UsbDirection::Out));
+    assert_eq!(ep_out.max_burst, Some(3));
+}
+
+#[wasm_bindgen_test]
+fn interleaved_alternate_settings() {
+    const CONFIG: &[u8] = &[
+        0x09, 0x02, 0x35, 0x00, 0x02, 0x01, 0x00, 0x80, 0x32, // configuration
+        0x09, 0x04, 0x00, 0x00, 0x00, 0xff, 0x00, 0x00, 0x00, // interface 0, alternate 0
+        0x09, 0x04, 0x01, 0x00, 0x01, 0xff, 0x00, 0x00, 0x00, // interface 1, alternate 0
+        0x07, 0x05, 0x83, 0x03, 0x08, 0x00, 0x01, // interrupt in endpoint
+        0x09, 0x04, 0x00, 0x01, 0x02, 0xff, 0x00, 0x00, 0x00, // interface 0, alternate 1
+        0x07, 0x05, 0x81, 0x02, 0x00, 0x02, 0x00, // bulk in endpoint
+        0x07, 0x05, 0x02, 0x02, 0x00, 0x02, 0x00, // bulk out endpoint
+    ];
+
+    let cfg = UsbConfiguration::from_raw_descriptor(CONFIG).unwrap();
+    assert_eq!(cfg.interfaces.len(), 2);
+
+    let if0 = &cfg.interfaces[0];
+    assert_eq!(if0.alternates.len(), 2);
+    assert!(if0.alternates[0].endpoints.is_empty());
+    let eps: Vec<_> = if0.alternates[1].endpoints.iter().map(|ep| (ep.endpoint_number, ep.direction)).collect();
+    assert_eq!(eps, [(1, UsbDirection::In), (2, UsbDirection::Out)]);
+
+    let if1 = &cfg.interfaces[1];
+    assert_eq!(if1.alternates.len(), 1);
+    assert_eq!(if1.alternates[0].endpoints.len(), 1);
+    assert_eq!(if1.alternates[0].endpoints[0].endpoint_number, 3);
+}
+
+#[wasm_bindgen_test]
+fn invalid_configuration() {
+    assert_eq!(
+        UsbConfiguration::from_raw_descriptor(&SUPERSPEED_CONFIG[9..]).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+}
+
+#[wasm_bindgen_test]
+fn power_attributes() {
+    // Bus-powered high-speed configuration with remote wakeup drawing 100 mA.
+    const CONFIG: &[u8] = &[
+        0x09, 0x02, 0x12, 0x00, 0x01, 0x01, 0x00, 0xa0, 0x32, // configuration
+        0x09, 0x04, 0x00, 0x00, 0x00, 0xff, 0x00, 0x00, 0x00, // interface
+    ];
+    let cfg = UsbConfiguration::from_raw_descriptor(CONFIG).unwrap();
+    assert_eq!(cfg.self_powered, Some(false));
+    assert_eq!(cfg.remote_wakeup, Some(true));
+    assert_eq!(cfg.max_power_ma, Some(100));
+
+    // Bus-powered SuperSpeed configuration drawing 400 mA.
+    let cfg = UsbConfiguration::from_raw_descriptor(SUPERSPEED_CONFIG).unwrap();
+    assert_eq!(cfg.self_powered, Some(false));
+    assert_eq!(cfg.remote_wakeup, Some(false));
+    assert_eq!(cfg.max_power_ma, Some(400));
+}
+
+/// Composite configuration with a CDC-ACM function grouped by an interface association
+/// and a vendor-specific interface.
+const IAD_CONFIG: &[u8] = &[
+    0x09, 0x02, 0x3b, 0x00, 0x03, 0x01, 0x00, 0x80, 0x32, // configuration
+    0x08, 0x0b, 0x00, 0x02, 0x02, 0x02, 0x01, 0x00, // interface association
+    0x09, 0x04, 0x00, 0x00, 0x01, 0x02, 0x02, 0x01, 0x00, // CDC communication interface
+    0x07, 0x05, 0x83, 0x03, 0x08, 0x00, 0x10, // interrupt in endpoint
+    0x09, 0x04, 0x01, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, // CDC data interface
+    0x09, 0x04, 0x02, 0x00, 0x00, 0xff, 0x01, 0x02, 0x00, // vendor interface
+];
+
+#[wasm_bindgen_test]
+fn functions_with_iad() {
+    let cfg = UsbConfiguration::from_raw_descriptor(IAD_CONFIG).unwrap();
+    assert_eq!(cfg.interface_associations.len(), 1);
+
+    let functions = cfg.functions();
+    assert_eq!(functions.len(), 2);
+    assert_eq!(functions[0].interfaces, [0, 1]);
+    assert_eq!((functions[0].class, functions[0].subclass, functions[0].protocol), (0x02, 0x02, 0x01));
+    assert_eq!(functions[1].interfaces, [2]);
+    assert_eq!((functions[1].class, functions[1].subclass, functions[1].protocol), (0xff, 0x01, 0x02));
+}
+
+#[wasm_bindgen_test]
+fn functions_without_iad() {
+    // Same configuration without the interface association descriptor.
+    let raw = [&IAD_CONFIG[..9], &IAD_CONFIG[17..]].concat();
+    let cfg = UsbConfiguration::from_raw_descriptor(&raw).unwrap();
+    assert!(cfg.interface_associations.is_empty());
+
+    let functions = cfg.functions();
+    let interfaces: Vec<_> = functions.iter().map(|f| f.interfaces.clone()).collect();
+    assert_eq!(interfaces, [vec![0], vec![1], vec![2]]);
+    assert_eq!(functions[1].class, 0x0a);
+}
+
+#[wasm_bindgen_test]
+fn endpoint_interval() {
+    let cfg = UsbConfiguration::from_raw_descriptor(IAD_CONFIG).unwrap();
+    let ep = &cfg.interfaces[0].alternate.endpoints[0];
+    assert_eq!(ep.endpoint_type, UsbEndpointType::Interrupt);
+    assert_eq!(ep.interval, Some(0x10));
+
+    let cfg = UsbConfiguration::from_raw_descriptor(SUPERSPEED_CONFIG).unwrap();
+    assert_eq!(cfg.interfaces[0].alternate.endpoints[0].interval, Some(0));
+}
+
+#[wasm_bindgen_test]
+fn endpoint_raw_attributes() {
+    for raw in [IAD_CONFIG, SUPERSPEED_CONFIG] {
+        let cfg = UsbConfiguration::from_raw_descriptor(raw).unwrap();
+        for (_, _, ep) in cfg.iter_endpoints() {
+            let expected = match ep.endpoint_type {
+                UsbEndpointType::Isochronous => 0x01,
+                UsbEndpointType::Bulk => 0x02,
+                UsbEndpointType::Interrupt => 0x03,
+            };
+            assert_eq!(ep.raw_attributes.unwrap() & 0x03, expected);
+        }
+    }
+}
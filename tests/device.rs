@@ -0,0 +1,758 @@
+use std::time::Duration;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+mod mock;
+
+use webusb_web::*;
+
+async fn open_configured() -> OpenUsbDevice {
+    mock::install_classes();
+    let dev = mock::device(&format!("{{ {} {} }}", mock::OPEN_CLOSE, mock::CONFIGURED));
+    dev.open().await.unwrap()
+}
+
+#[wasm_bindgen_test]
+async fn max_packet_size() {
+    let open = open_configured().await;
+
+    assert_eq!(open.max_packet_size(1, UsbDirection::In), Some(512));
+    assert_eq!(open.max_packet_size(2, UsbDirection::Out), Some(512));
+    assert_eq!(open.max_packet_size(1, UsbDirection::Out), None);
+    assert_eq!(open.max_packet_size(3, UsbDirection::In), None, "interface is not claimed");
+}
+
+#[wasm_bindgen_test]
+async fn close_timeout() {
+    let open = open_configured().await;
+    open.close_timeout(Duration::from_secs(1)).await.unwrap();
+
+    let dev = mock::device(
+        "{ opened: false, open() { this.opened = true; return Promise.resolve(); }, close() { return new Promise(() => {}); } }",
+    );
+    let open = dev.open().await.unwrap();
+    let err = open.close_timeout(Duration::from_millis(10)).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+}
+
+#[wasm_bindgen_test]
+async fn claimed_interfaces() {
+    let open = open_configured().await;
+    assert!(open.claimed_interfaces().is_empty());
+
+    open.claim_interface(1).await.unwrap();
+    open.claim_interface(0).await.unwrap();
+    assert_eq!(open.claimed_interfaces(), [0, 1]);
+
+    open.release_interface(0).await.unwrap();
+    assert_eq!(open.claimed_interfaces(), [1]);
+}
+
+#[wasm_bindgen_test]
+async fn configuration_cache() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            reads: 0,
+            get configurations() {{
+                this.reads++;
+                return [new USBConfiguration(1, [new USBInterface(0, [new USBAlternateInterface(0, [0xff, 0, 0], [])])])];
+            }},
+            selectAlternateInterface() {{ return Promise.resolve(); }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let reads = || js_sys::Reflect::get(dev.as_ref(), &"reads".into()).unwrap().as_f64().unwrap();
+
+    let first = dev.configurations();
+    assert_eq!(reads(), 1.);
+    let second = dev.clone().configurations();
+    assert_eq!(reads(), 1., "cached configurations must be reused");
+    assert_eq!(first[0].configuration_value, second[0].configuration_value);
+
+    let open = dev.open().await.unwrap();
+    open.select_alternate_interface(0, 0).await.unwrap();
+    dev.configurations();
+    assert_eq!(reads(), 2., "cache must be invalidated by selecting an alternate setting");
+}
+
+#[wasm_bindgen_test]
+async fn configuration_cache_shared() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            reads: 0,
+            get configurations() {{
+                this.reads++;
+                return [new USBConfiguration(1, [new USBInterface(0, [new USBAlternateInterface(0, [0xff, 0, 0], [])])])];
+            }},
+            reset() {{ return Promise.resolve(); }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let reads = || js_sys::Reflect::get(dev.as_ref(), &"reads".into()).unwrap().as_f64().unwrap();
+    let other = UsbDevice::from(dev.as_ref().clone());
+
+    dev.configurations();
+    other.configurations();
+    assert_eq!(reads(), 1., "wrappers of the same device must share the cache");
+
+    let open = other.open().await.unwrap();
+    open.claim_interface(0).await.unwrap();
+    dev.configurations();
+    assert_eq!(reads(), 2., "claiming through another wrapper must invalidate the cache");
+
+    open.reset().await.unwrap();
+    dev.configurations();
+    assert_eq!(reads(), 3., "cache must be invalidated by resetting");
+}
+
+#[wasm_bindgen_test]
+async fn open_busy() {
+    let dev = mock::device(
+        "{ opened: false, open() { return Promise.reject(new DOMException('The device is busy.', 'NetworkError')); } }",
+    );
+    let err = dev.open().await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyOpen);
+    assert_eq!(err.msg(), "The device is busy.");
+
+    let dev = mock::device(
+        "{ opened: false, open() { return Promise.reject(new DOMException('Access denied.', 'SecurityError')); } }",
+    );
+    assert_eq!(dev.open().await.unwrap_err().kind(), ErrorKind::Security);
+
+    let dev = mock::device("{ opened: true }");
+    assert_eq!(dev.open().await.unwrap_err().kind(), ErrorKind::AlreadyOpen);
+}
+
+#[wasm_bindgen_test]
+fn interface_for_endpoint() {
+    mock::install_classes();
+    let dev = mock::device(&format!("{{ {} }}", mock::CONFIGURED));
+    let cfg = dev.configuration().unwrap();
+
+    let iface = |ep, dir| cfg.interface_for_endpoint(ep, dir).map(|iface| iface.interface_number);
+    assert_eq!(iface(1, UsbDirection::In), Some(0));
+    assert_eq!(iface(2, UsbDirection::Out), Some(0));
+    assert_eq!(iface(3, UsbDirection::In), Some(1));
+    assert_eq!(iface(3, UsbDirection::Out), None);
+    assert_eq!(iface(4, UsbDirection::In), None);
+}
+
+/// Mock device recording the performed setup calls and failing to claim interface 1.
+const RECORDING: &str = "
+    opened: false,
+    calls: [],
+    open() { this.opened = true; this.calls.push('open'); return Promise.resolve(); },
+    close() { this.opened = false; this.calls.push('close'); return Promise.resolve(); },
+    selectConfiguration(cfg) { this.calls.push(`cfg ${cfg}`); return Promise.resolve(); },
+    claimInterface(iface) {
+        this.calls.push(`claim ${iface}`);
+        return iface == 1 ? Promise.reject(new DOMException('Unable to claim interface.', 'NetworkError'))
+                          : Promise.resolve();
+    },
+    selectAlternateInterface(iface, alt) { this.calls.push(`alt ${iface} ${alt}`); return Promise.resolve(); },
+";
+
+fn calls(dev: &UsbDevice) -> Vec<String> {
+    let calls = js_sys::Reflect::get(dev.as_ref(), &"calls".into()).unwrap();
+    js_sys::Array::from(&calls).iter().map(|call| call.as_string().unwrap()).collect()
+}
+
+#[wasm_bindgen_test]
+async fn open_builder() {
+    let dev = mock::device(&format!("{{ {RECORDING} }}"));
+    let open = dev.open_builder().configuration(1).claim(0).alternate(0, 1).open().await.unwrap();
+    assert_eq!(calls(&dev), ["open", "cfg 1", "claim 0", "alt 0 1"]);
+    assert_eq!(open.claimed_interfaces(), [0]);
+    open.close().await.unwrap();
+
+    let dev = mock::device(&format!("{{ {RECORDING} }}"));
+    let err = dev.open_builder().claim(0).claim(1).alternate(1, 1).open().await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Transfer);
+    assert_eq!(calls(&dev), ["open", "claim 0", "claim 1", "close"]);
+    assert!(!dev.opened());
+}
+
+#[wasm_bindgen_test]
+fn weak_device() {
+    mock::install_classes();
+    let dev = mock::device(&format!("{{ {} {} }}", mock::OPEN_CLOSE, mock::CONFIGURED));
+    let weak = dev.downgrade();
+
+    let upgraded = weak.upgrade().unwrap();
+    assert_eq!(upgraded, dev);
+    assert_eq!(upgraded.configuration().unwrap().configuration_value, 1);
+}
+
+#[wasm_bindgen_test]
+fn active_endpoints() {
+    mock::install_classes();
+    let dev = mock::device(&format!("{{ {} {} }}", mock::OPEN_CLOSE, mock::CONFIGURED));
+
+    let endpoints: Vec<_> =
+        dev.active_endpoints().into_iter().map(|(iface, ep)| (iface, ep.endpoint_number, ep.direction)).collect();
+    assert_eq!(endpoints, [(0, 1, UsbDirection::In), (0, 2, UsbDirection::Out), (1, 3, UsbDirection::In)]);
+
+    let dev = mock::device(&format!("{{ {} configuration: null, configurations: [] }}", mock::OPEN_CLOSE));
+    assert!(dev.active_endpoints().is_empty());
+}
+
+#[wasm_bindgen_test]
+async fn select_alternate_interface_checked() {
+    mock::install_classes();
+    let dev = mock::device(&format!("{{ {RECORDING} {} }}", mock::CONFIGURED));
+    let open = dev.open().await.unwrap();
+
+    let err = open.select_alternate_interface_checked(0, 1).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidAccess);
+    assert!(err.msg().ends_with("valid are: 0"), "{}", err.msg());
+
+    let err = open.select_alternate_interface_checked(5, 0).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidAccess);
+
+    open.select_alternate_interface_checked(0, 0).await.unwrap();
+    assert_eq!(calls(&dev), ["open", "alt 0 0"]);
+}
+
+/// Mock device members of a configuration with the specified interfaces, recording releases.
+fn with_interfaces(numbers: &[u8]) -> String {
+    let interfaces = numbers
+        .iter()
+        .map(|n| format!("new USBInterface({n}, [new USBAlternateInterface(0, [0xff, 0, 0], [])])"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "get configuration() {{ return this.configurations[0]; }},
+        configurations: [new USBConfiguration(1, [{interfaces}])],
+        releaseInterface(iface) {{ this.calls.push(`release ${{iface}}`); return Promise.resolve(); }},"
+    )
+}
+
+#[wasm_bindgen_test]
+async fn claim_all_interfaces() {
+    mock::install_classes();
+
+    let dev = mock::device(&format!("{{ {RECORDING} {} }}", with_interfaces(&[0, 2])));
+    let open = dev.open().await.unwrap();
+    assert_eq!(open.claim_all_interfaces().await.unwrap(), [0, 2]);
+    assert_eq!(open.claimed_interfaces(), [0, 2]);
+    assert_eq!(calls(&dev), ["open", "claim 0", "claim 2"]);
+
+    // Claiming interface 1 fails.
+    let dev = mock::device(&format!("{{ {RECORDING} {} }}", with_interfaces(&[0, 1, 2])));
+    let open = dev.open().await.unwrap();
+    let err = open.claim_all_interfaces().await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Transfer);
+    assert!(open.claimed_interfaces().is_empty());
+    assert_eq!(calls(&dev), ["open", "claim 0", "claim 1", "release 0"]);
+}
+
+#[wasm_bindgen_test]
+fn speed() {
+    let speed = |major: u8| mock::device(&format!("{{ usbVersionMajor: {major} }}")).speed();
+    assert_eq!(speed(0), UsbSpeed::Unknown);
+    assert_eq!(speed(1), UsbSpeed::Full);
+    assert_eq!(speed(2), UsbSpeed::High);
+    assert_eq!(speed(3), UsbSpeed::Super);
+    assert_eq!(speed(4), UsbSpeed::Super);
+}
+
+#[wasm_bindgen_test]
+fn descriptor_eq() {
+    mock::install_classes();
+    const DESCRIPTORS: &str = "
+        vendorId: 1, productId: 2, deviceClass: 0, deviceSubclass: 0, deviceProtocol: 0,
+        deviceVersionMajor: 1, deviceVersionMinor: 0, deviceVersionSubminor: 0,
+        usbVersionMajor: 2, usbVersionMinor: 0, usbVersionSubminor: 0,
+        manufacturerName: 'ACME', productName: 'Widget', serialNumber: '1234',
+    ";
+    let dev = mock::device(&format!("{{ {DESCRIPTORS} {} }}", mock::CONFIGURED));
+    let same = mock::device(&format!("{{ {DESCRIPTORS} {} }}", mock::CONFIGURED));
+    assert_ne!(dev, same);
+    assert!(dev.descriptor_eq(&dev));
+    assert!(dev.descriptor_eq(&same));
+
+    let other_version = mock::device(&format!("{{ {DESCRIPTORS} {} deviceVersionMinor: 1 }}", mock::CONFIGURED));
+    assert!(!dev.descriptor_eq(&other_version));
+
+    let other_endpoint =
+        mock::device(&format!("{{ {DESCRIPTORS} {} }}", mock::CONFIGURED.replace("'bulk', 512", "'bulk', 64")));
+    assert!(!dev.descriptor_eq(&other_endpoint));
+}
+
+#[wasm_bindgen_test]
+async fn get_interface() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} {}
+            alternates: {{}},
+            selectAlternateInterface(iface, alt) {{ this.alternates[iface] = alt; return Promise.resolve(); }},
+            controlTransferIn(setup, len) {{
+                if (setup.requestType != 'standard' || setup.recipient != 'interface' || setup.request != 0x0a)
+                    return Promise.resolve(new USBInTransferResult('stall', undefined));
+                const data = new Uint8Array(setup.index == 3 ? [] : [this.alternates[setup.index] ?? 0]);
+                return Promise.resolve(new USBInTransferResult('ok', new DataView(data.buffer)));
+            }},
+        }}",
+        mock::OPEN_CLOSE,
+        mock::CONFIGURED
+    ));
+    let open = dev.open().await.unwrap();
+
+    assert_eq!(open.get_interface(1).await.unwrap(), 0);
+    open.select_alternate_interface(1, 2).await.unwrap();
+    assert_eq!(open.get_interface(1).await.unwrap(), 2);
+    assert_eq!(open.get_interface(0).await.unwrap(), 0);
+    assert_eq!(open.get_interface(3).await.unwrap_err().kind(), ErrorKind::InvalidData);
+}
+
+#[wasm_bindgen_test]
+fn interface_names() {
+    mock::install_classes();
+    let dev = mock::device(
+        "{ configurations: [
+            new USBConfiguration(1, [
+                new USBInterface(0, [new USBAlternateInterface(0, [0xff, 0, 0], [], 'Data')]),
+                new USBInterface(1, [new USBAlternateInterface(0, [0xff, 0, 0], [])]),
+                new USBInterface(2, [new USBAlternateInterface(0, [0xff, 0, 0], [], 'Control')]),
+            ]),
+        ] }",
+    );
+    let cfg = &dev.configurations()[0];
+
+    assert_eq!(
+        cfg.interface_names(),
+        [(0, Some("Data".to_string())), (1, None), (2, Some("Control".to_string()))]
+    );
+}
+
+#[wasm_bindgen_test]
+async fn try_reset() {
+    let dev = mock::device(&format!("{{ {} }}", mock::OPEN_CLOSE));
+    let open = dev.open().await.unwrap();
+    assert!(!open.try_reset().await.unwrap());
+
+    let dev = mock::device(&format!(
+        "{{ {} reset() {{ return Promise.reject(new DOMException('Reset not supported.', 'NotSupportedError')); }} }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+    assert!(!open.try_reset().await.unwrap());
+
+    let dev = mock::device(&format!(
+        "{{ {} reset() {{ return Promise.reject(new DOMException('Unable to reset the device.', 'NetworkError')); }} }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+    assert_eq!(open.try_reset().await.unwrap_err().kind(), ErrorKind::Transfer);
+
+    let dev = mock::device(&format!("{{ {} reset() {{ return Promise.resolve(); }} }}", mock::OPEN_CLOSE));
+    let open = dev.open().await.unwrap();
+    assert!(open.try_reset().await.unwrap());
+}
+
+#[wasm_bindgen_test]
+async fn is_endpoint_halted() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} {}
+            controlTransferIn(setup, len) {{
+                if (setup.requestType != 'standard' || setup.recipient != 'endpoint' || setup.request != 0 || len != 2)
+                    return Promise.resolve(new USBInTransferResult('stall', undefined));
+                const data = new Uint8Array(setup.index == 0x03 ? [0] : [setup.index == 0x02 ? 1 : 0, 0]);
+                return Promise.resolve(new USBInTransferResult('ok', new DataView(data.buffer)));
+            }},
+        }}",
+        mock::OPEN_CLOSE,
+        mock::CONFIGURED
+    ));
+    let open = dev.open().await.unwrap();
+
+    assert!(!open.is_endpoint_halted(1, UsbDirection::In).await.unwrap());
+    assert!(open.is_endpoint_halted(2, UsbDirection::Out).await.unwrap());
+    assert_eq!(open.is_endpoint_halted(3, UsbDirection::Out).await.unwrap_err().kind(), ErrorKind::InvalidData);
+}
+
+#[wasm_bindgen_test]
+fn endpoints_of() {
+    mock::install_classes();
+    let dev = mock::device(&format!("{{ {} }}", mock::CONFIGURED));
+
+    assert_eq!(dev.endpoints_of(UsbEndpointType::Bulk, UsbDirection::In), [(0, 1)]);
+    assert_eq!(dev.endpoints_of(UsbEndpointType::Bulk, UsbDirection::Out), [(0, 2)]);
+    assert_eq!(dev.endpoints_of(UsbEndpointType::Interrupt, UsbDirection::In), [(1, 3)]);
+    assert!(dev.endpoints_of(UsbEndpointType::Isochronous, UsbDirection::In).is_empty());
+}
+
+#[wasm_bindgen_test]
+async fn claim_interface_by_class() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            configuration: new USBConfiguration(1, [
+                new USBInterface(0, [new USBAlternateInterface(0, [0x02, 0x02, 0x01], [])]),
+                new USBInterface(1, [new USBAlternateInterface(0, [0x0a, 0x00, 0x00], [])]),
+                new USBInterface(2, [new USBAlternateInterface(0, [0xff, 0x01, 0x02], [])]),
+                new USBInterface(3, [new USBAlternateInterface(0, [0xff, 0x02, 0x02], [])]),
+            ]),
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let guard = open.claim_interface_by_class(0xff, Some(0x02), None).await.unwrap();
+    assert_eq!(guard.interface(), 3);
+    let data = open.claim_interface_by_class(0x0a, None, None).await.unwrap();
+    assert_eq!(data.interface(), 1);
+    assert_eq!(open.claimed_interfaces(), [1, 3]);
+
+    drop(guard);
+    assert_eq!(open.claimed_interfaces(), [1]);
+    data.release().await.unwrap();
+    assert!(open.claimed_interfaces().is_empty());
+
+    let err = open.claim_interface_by_class(0xff, Some(0x01), Some(0x01)).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidAccess);
+}
+
+#[wasm_bindgen_test]
+fn conflicting_endpoints() {
+    mock::install_classes();
+    let dev = mock::device(
+        "{ configurations: [
+            new USBConfiguration(1, [
+                new USBInterface(0, [new USBAlternateInterface(0, [0xff, 0, 0], [
+                    new USBEndpoint(1, 'in', 'bulk', 512),
+                    new USBEndpoint(2, 'out', 'bulk', 512),
+                ])], true),
+                new USBInterface(1, [new USBAlternateInterface(0, [0xff, 0, 0], [
+                    new USBEndpoint(1, 'out', 'bulk', 512),
+                    new USBEndpoint(3, 'in', 'interrupt', 8),
+                ])], true),
+                new USBInterface(2, [new USBAlternateInterface(0, [0xff, 0, 0], [
+                    new USBEndpoint(2, 'in', 'bulk', 512),
+                ])]),
+            ]),
+        ] }",
+    );
+    let cfg = &dev.configurations()[0];
+
+    assert!(cfg.has_endpoint_conflicts());
+    assert_eq!(cfg.conflicting_endpoints(), [1]);
+
+    let dev = mock::device(&format!("{{ {} }}", mock::CONFIGURED));
+    assert!(!dev.configuration().unwrap().has_endpoint_conflicts());
+}
+
+#[wasm_bindgen_test]
+async fn into_device() {
+    let dev = mock::device(&format!("{{ {} }}", mock::OPEN_CLOSE));
+
+    let open = dev.open().await.unwrap();
+    let closed = open.close_and_into_device().await.unwrap();
+    assert_eq!(closed, dev);
+    assert!(!dev.opened());
+
+    let open = dev.open().await.unwrap();
+    let kept = open.into_device_keep_open();
+    assert_eq!(kept, dev);
+    // Give a wrongly spawned close the chance to run.
+    wasm_bindgen_futures::JsFuture::from(js_sys::Promise::resolve(&wasm_bindgen::JsValue::NULL)).await.unwrap();
+    assert!(dev.opened());
+}
+
+#[wasm_bindgen_test]
+async fn clear_halt_on() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} {} calls: [], clearHalt(dir, ep) {{ this.calls.push(`${{dir}} ${{ep}}`); return Promise.resolve(); }} }}",
+        mock::OPEN_CLOSE,
+        mock::CONFIGURED
+    ));
+    let open = dev.open().await.unwrap();
+
+    open.clear_halt_on(0, UsbDirection::Out, 2).await.unwrap();
+    let err = open.clear_halt_on(1, UsbDirection::In, 1).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidAccess);
+    assert_eq!(err.msg(), "In endpoint 1 does not belong to interface 1");
+    let err = open.clear_halt_on(0, UsbDirection::Out, 1).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidAccess);
+    assert_eq!(calls(&dev), ["out 2"]);
+}
+
+#[wasm_bindgen_test]
+async fn recommended_max_transfer() {
+    let open = open_configured().await;
+
+    for endpoint_type in [UsbEndpointType::Bulk, UsbEndpointType::Interrupt, UsbEndpointType::Isochronous] {
+        let max = open.recommended_max_transfer(endpoint_type);
+        assert!(max >= 1024, "{endpoint_type:?}: {max}");
+        assert!(max <= 16 << 20, "{endpoint_type:?}: {max}");
+        assert_eq!(max % 1024, 0, "{endpoint_type:?}: {max}");
+    }
+    assert!(
+        open.recommended_max_transfer(UsbEndpointType::Bulk)
+            >= open.recommended_max_transfer(UsbEndpointType::Interrupt)
+    );
+}
+
+#[wasm_bindgen_test]
+async fn interface_handle() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} {}
+            transferIn(ep, len) {{
+                return Promise.resolve(new USBInTransferResult('ok', new DataView(new Uint8Array([ep]).buffer)));
+            }},
+            transferOut(ep, data) {{ return Promise.resolve(new USBOutTransferResult('ok', data.byteLength)); }},
+        }}",
+        mock::OPEN_CLOSE,
+        mock::CONFIGURED
+    ));
+    let open = dev.open().await.unwrap();
+
+    let data = open.interface_handle(0).await.unwrap();
+    let notify = open.interface_handle(1).await.unwrap();
+    assert_eq!((data.interface(), notify.interface()), (0, 1));
+    assert_eq!(open.claimed_interfaces(), [0, 1]);
+
+    assert_eq!(data.transfer_in(1, 8).await.unwrap(), [1]);
+    assert_eq!(data.transfer_out(2, &[0; 4]).await.unwrap(), 4);
+    assert_eq!(notify.transfer_in(3, 8).await.unwrap(), [3]);
+
+    assert_eq!(data.transfer_in(3, 8).await.unwrap_err().kind(), ErrorKind::InvalidAccess);
+    assert_eq!(notify.transfer_in(1, 8).await.unwrap_err().kind(), ErrorKind::InvalidAccess);
+    assert_eq!(notify.transfer_out(3, &[0]).await.unwrap_err().kind(), ErrorKind::InvalidAccess);
+
+    drop(notify);
+    assert_eq!(open.claimed_interfaces(), [0]);
+    data.release().await.unwrap();
+    assert!(open.claimed_interfaces().is_empty());
+}
+
+#[wasm_bindgen_test]
+async fn try_forget() {
+    let dev = mock::device(
+        "{ forgets: 0, forget() {
+            return ++this.forgets == 1 ? Promise.reject(new DOMException('Failed to forget.', 'NetworkError'))
+                                       : Promise.resolve();
+        } }",
+    );
+
+    assert_eq!(dev.try_forget().await.unwrap_err().kind(), ErrorKind::Transfer);
+    dev.try_forget().await.unwrap();
+
+    let dev =
+        mock::device("{ forget() { return Promise.reject(new DOMException('Failed.', 'NetworkError')); } }");
+    assert!(dev.forget().await.is_err());
+}
+
+#[wasm_bindgen_test]
+fn validate() {
+    mock::install_classes();
+    let dev = mock::device(&format!("{{ {} }}", mock::CONFIGURED));
+
+    let bulk = InterfaceExpectation::new(0)
+        .with_class_code(0xff)
+        .with_endpoint(1, UsbDirection::In)
+        .with_endpoint(2, UsbDirection::Out);
+    dev.validate(&DeviceExpectation::new().with_interface(bulk.clone())).unwrap();
+
+    let hid = InterfaceExpectation::new(1).with_class_code(0x03).with_endpoint(4, UsbDirection::In);
+    let err =
+        dev.validate(&DeviceExpectation::new().with_interface(bulk.clone()).with_interface(hid)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+    assert_eq!(err.msg(), "device does not match expectation: interface 1 lacks In endpoint 4");
+
+    let expected = DeviceExpectation::new()
+        .with_interface(bulk.with_protocol_code(1))
+        .with_interface(InterfaceExpectation::new(2));
+    let err = dev.validate(&expected).unwrap_err();
+    assert_eq!(
+        err.msg(),
+        "device does not match expectation: interface 0 has protocol 0x00 instead of 0x01, interface 2 is missing"
+    );
+}
+
+#[wasm_bindgen_test]
+async fn describe() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ vendorId: 0x1234, productId: 0xabcd, deviceClass: 0, deviceSubclass: 0, deviceProtocol: 0,
+            usbVersionMajor: 2, usbVersionMinor: 0, usbVersionSubminor: 0,
+            deviceVersionMajor: 1, deviceVersionMinor: 2, deviceVersionSubminor: 3,
+            manufacturerName: 'ACME', productName: 'Widget', serialNumber: null, {} }}",
+        mock::CONFIGURED
+    ));
+    let text = dev.describe();
+
+    assert!(text.starts_with("Device 1234:abcd \"ACME\" \"Widget\"\n"), "{text}");
+    assert!(text.contains("USB 2.0.0, device version 1.2.3"), "{text}");
+    assert!(text.contains("serial number none"), "{text}");
+
+    assert!(text.contains("Configuration 1 (active)"), "{text}");
+    assert!(text.contains("Interface 0 alternate 0: class 0xff/0x00/0x00"), "{text}");
+    assert!(text.contains("Endpoint 0x81 In Bulk, packet size 512"), "{text}");
+    assert!(text.contains("Endpoint 0x02 Out Bulk, packet size 512"), "{text}");
+    assert!(text.contains("Endpoint 0x83 In Interrupt, packet size 8"), "{text}");
+}
+
+#[wasm_bindgen_test]
+async fn duplex_pair() {
+    let open = open_configured().await;
+    let cfg = open.device().configuration().unwrap();
+
+    assert_eq!(cfg.interfaces[0].alternate.duplex_pair(), Some((1, 2)));
+    assert_eq!(cfg.interfaces[1].alternate.duplex_pair(), None);
+}
+
+#[wasm_bindgen_test]
+fn configuration_value_at() {
+    mock::install_classes();
+    let dev = mock::device(
+        "{ configuration: null, configurations: [new USBConfiguration(2, []), new USBConfiguration(5, [])] }",
+    );
+
+    assert_eq!(dev.configuration_value_at(0), Some(2));
+    assert_eq!(dev.configuration_value_at(1), Some(5));
+    assert_eq!(dev.configuration_value_at(2), None);
+}
+
+#[wasm_bindgen_test]
+async fn open_twice() {
+    let dev = mock::device(
+        "{ opened: false, opens: 0,
+           open() { this.opens++; this.opened = true; return Promise.resolve(); },
+           close() { return new Promise(resolve => setTimeout(() => { this.opened = false; resolve(); }, 20)); } }",
+    );
+    let opens = || js_sys::Reflect::get(dev.as_ref(), &"opens".into()).unwrap().as_f64().unwrap();
+
+    let open = dev.open().await.unwrap();
+    assert!(dev.opened_handle());
+    let err = dev.open().await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyOpen);
+
+    // Dropping closes in the background, reopening waits for it.
+    drop(open);
+    assert!(dev.opened());
+    assert!(!dev.opened_handle());
+    let open = dev.open().await.unwrap();
+    assert_eq!(opens(), 2.);
+
+    // A device kept open is taken over without reopening it.
+    let dev = open.into_device_keep_open();
+    assert!(!dev.opened_handle());
+    let open = dev.open().await.unwrap();
+    assert!(dev.opened_handle());
+    assert_eq!(opens(), 2.);
+    open.close().await.unwrap();
+    assert!(!dev.opened_handle());
+}
+
+#[wasm_bindgen_test]
+async fn open_cancelled() {
+    let dev = mock::device(
+        "{ opened: false, opens: 0, closes: 0,
+           open() {
+               if (++this.opens > 1) { this.opened = true; return Promise.resolve(); }
+               return new Promise(resolve => { this.finishOpen = () => { this.opened = true; resolve(); }; });
+           },
+           close() { this.closes++; this.opened = false; return Promise.resolve(); } }",
+    );
+    let get = |name: &str| js_sys::Reflect::get(dev.as_ref(), &name.into()).unwrap();
+
+    // Drop the opening future while the browser is still opening the device.
+    let res = futures_util::future::select(Box::pin(dev.open()), std::future::ready(())).await;
+    assert!(matches!(res, futures_util::future::Either::Right(_)));
+    drop(res);
+    assert!(!dev.opened_handle());
+
+    let finish_open: js_sys::Function = get("finishOpen").unchecked_into();
+    finish_open.call0(dev.as_ref()).unwrap();
+
+    let open = dev.open().await.unwrap();
+    assert!(dev.opened_handle());
+    assert_eq!(get("opens").as_f64(), Some(2.));
+    assert_eq!(get("closes").as_f64(), Some(1.), "device must be closed after cancelled opening");
+    open.close().await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn endpoint_map() {
+    let open = open_configured().await;
+    let map = open.build_endpoint_map();
+
+    assert_eq!(map.bulk_in(0), Some(1));
+    assert_eq!(map.bulk_out(0), Some(2));
+    assert_eq!(map.interrupt_in(1), Some(3));
+    assert_eq!(map.get(1, UsbDirection::In, UsbEndpointType::Interrupt), Some(3));
+    assert_eq!(map.bulk_in(1), None);
+    assert_eq!(map.interrupt_out(1), None);
+    assert_eq!(map.bulk_in(2), None);
+}
+
+#[wasm_bindgen_test]
+fn usb_version_bcd() {
+    let dev = mock::device("{ usbVersionMajor: 2, usbVersionMinor: 1, usbVersionSubminor: 0 }");
+    assert_eq!(dev.usb_version_bcd(), 0x0210);
+    assert!(dev.usb_version_bcd() < 0x0300);
+
+    let dev = mock::device("{ usbVersionMajor: 3, usbVersionMinor: 2, usbVersionSubminor: 1 }");
+    assert_eq!(dev.usb_version_bcd(), 0x0321);
+    assert!(dev.usb_version_bcd() >= 0x0300);
+}
+
+#[wasm_bindgen_test]
+async fn endpoints_of_active_alternate() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            configuration: new USBConfiguration(1, [
+                new USBInterface(0, [
+                    new USBAlternateInterface(0, [0xff, 0, 0], []),
+                    new USBAlternateInterface(1, [0xff, 0, 0], [
+                        new USBEndpoint(1, 'in', 'isochronous', 1024),
+                        new USBEndpoint(2, 'out', 'isochronous', 1024),
+                    ]),
+                ], true),
+            ]),
+            selectAlternateInterface(iface, alt) {{
+                const intf = this.configuration.interfaces[iface];
+                intf.alternate = intf.alternates[alt];
+                return Promise.resolve();
+            }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+    let stale = dev.configuration().unwrap().interfaces[0].clone();
+
+    assert!(open.endpoints(0).unwrap().is_empty());
+    open.select_alternate_interface(0, 1).await.unwrap();
+
+    let endpoints = open.endpoints(0).unwrap();
+    assert_eq!(endpoints.len(), 2);
+    assert_eq!(endpoints[0].endpoint_number, 1);
+    assert_eq!(endpoints[0].endpoint_type, UsbEndpointType::Isochronous);
+    assert_eq!(endpoints[1].direction, UsbDirection::Out);
+    assert!(stale.alternate.endpoints.is_empty());
+
+    assert_eq!(open.endpoints(3).unwrap_err().kind(), ErrorKind::InvalidAccess);
+}
+
+#[wasm_bindgen_test]
+fn unknown_endpoint_type() {
+    mock::install_classes();
+    let dev = mock::device(
+        "{ configurations: [new USBConfiguration(1, [new USBInterface(0, [new USBAlternateInterface(0, [0xff, 0, 0], [
+            new USBEndpoint(1, 'in', 'bulk', 512),
+            new USBEndpoint(2, 'in', 'teleport', 512),
+            new USBEndpoint(3, 'sideways', 'bulk', 512),
+        ])])])] }",
+    );
+
+    let endpoints = &dev.configurations()[0].interfaces[0].alternate.endpoints;
+    assert_eq!(endpoints.len(), 1, "endpoints of unknown type or direction must be skipped");
+    assert_eq!(endpoints[0].endpoint_number, 1);
+}
@@ -0,0 +1,40 @@
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use webusb_web::*;
+
+#[wasm_bindgen_test]
+fn from_dom_name() {
+    assert_eq!(ErrorKind::from_dom_name("NotFoundError"), ErrorKind::Disconnected);
+    assert_eq!(ErrorKind::from_dom_name("SecurityError"), ErrorKind::Security);
+    assert_eq!(ErrorKind::from_dom_name("InvalidAccessError"), ErrorKind::InvalidAccess);
+    assert_eq!(ErrorKind::from_dom_name("NetworkError"), ErrorKind::Transfer);
+    assert_eq!(ErrorKind::from_dom_name("NotSupportedError"), ErrorKind::Unsupported);
+    assert_eq!(ErrorKind::from_dom_name("QuotaExceededError"), ErrorKind::QuotaExceeded);
+    assert_eq!(ErrorKind::from_dom_name("AbortError"), ErrorKind::Other);
+    assert_eq!(ErrorKind::from_dom_name(""), ErrorKind::Other);
+}
+
+#[wasm_bindgen_test]
+fn from_js_error() {
+    let js_error = js_sys::Error::new("device gone");
+    js_error.set_name("NotFoundError");
+
+    let err = Error::from(JsValue::from(js_error));
+    assert_eq!(err.kind(), ErrorKind::Disconnected);
+    assert_eq!(err.msg(), "device gone");
+}
+
+#[wasm_bindgen_test]
+fn quota_exceeded() {
+    let js_error = js_sys::Error::new("Too many open devices.");
+    js_error.set_name("InvalidStateError");
+
+    let err = Error::from(JsValue::from(js_error));
+    assert_eq!(err.kind(), ErrorKind::QuotaExceeded);
+    assert_eq!(std::io::Error::from(err).kind(), std::io::ErrorKind::QuotaExceeded);
+
+    let js_error = js_sys::Error::new("The device is busy.");
+    js_error.set_name("InvalidStateError");
+    assert_eq!(Error::from(JsValue::from(js_error)).kind(), ErrorKind::Other);
+}
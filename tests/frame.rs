@@ -0,0 +1,73 @@
+use futures_util::StreamExt;
+use std::pin::pin;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+mod mock;
+
+use webusb_web::{frame::FrameConfig, *};
+
+/// Opens a mock device whose endpoint 1 returns the specified chunks, one per transfer.
+async fn open_chunked(chunks: &[&[u8]]) -> OpenUsbDevice {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} chunks: {chunks:?}, transferIn(ep, len) {{
+            const chunk = this.chunks.shift();
+            if (chunk === undefined) return new Promise(() => {{}});
+            return Promise.resolve(new USBInTransferResult('ok', new DataView(new Uint8Array(chunk).buffer)));
+        }} }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+    open.set_default_read_len(64);
+    open
+}
+
+fn config() -> FrameConfig {
+    FrameConfig::new(4, 2, 2).with_magic([0xaa, 0x55]).with_max_frame_size(16)
+}
+
+#[wasm_bindgen_test]
+async fn split_frame() {
+    let open = open_chunked(&[&[0xaa, 0x55, 0x03], &[0x00, 1, 2, 3, 0xaa, 0x55, 0x01, 0x00, 9]]).await;
+    let mut frames = pin!(open.read_framed(1, config()));
+
+    assert_eq!(frames.next().await.unwrap().unwrap(), [0xaa, 0x55, 0x03, 0x00, 1, 2, 3]);
+    assert_eq!(frames.next().await.unwrap().unwrap(), [0xaa, 0x55, 0x01, 0x00, 9]);
+}
+
+#[wasm_bindgen_test]
+async fn resync_after_corrupt_header() {
+    let open = open_chunked(&[&[0x12, 0xaa, 0x55, 0xff, 0x00, 1, 2], &[0xaa], &[0x55, 0x01, 0x00, 7]]).await;
+    let mut frames = pin!(open.read_framed(1, config()));
+
+    assert_eq!(frames.next().await.unwrap().unwrap(), [0xaa, 0x55, 0x01, 0x00, 7]);
+}
+
+#[wasm_bindgen_test]
+async fn big_endian_without_magic() {
+    let open = open_chunked(&[&[0x00, 0x02, 5, 6, 0x00, 0x00]]).await;
+    let mut frames = pin!(open.read_framed(1, FrameConfig::new(2, 0, 2).with_big_endian(true)));
+
+    assert_eq!(frames.next().await.unwrap().unwrap(), [0x00, 0x02, 5, 6]);
+    assert_eq!(frames.next().await.unwrap().unwrap(), [0x00, 0x00]);
+}
+
+#[wasm_bindgen_test]
+async fn resync_without_magic() {
+    let mut second = vec![0xff; 20];
+    second.extend([0x02, 0x00, 5, 6, 0x01]);
+    let open = open_chunked(&[&[0xff; 40], &second, &[0x00, 9]]).await;
+    let mut frames = pin!(open.read_framed(1, FrameConfig::new(2, 0, 2).with_max_frame_size(16)));
+
+    assert_eq!(frames.next().await.unwrap().unwrap(), [0x02, 0x00, 5, 6]);
+    assert_eq!(frames.next().await.unwrap().unwrap(), [0x01, 0x00, 9]);
+}
+
+#[wasm_bindgen_test]
+async fn invalid_config() {
+    let open = open_chunked(&[]).await;
+    let mut frames = pin!(open.read_framed(1, FrameConfig::new(4, 2, 3)));
+
+    assert_eq!(frames.next().await.unwrap().unwrap_err().kind(), ErrorKind::InvalidAccess);
+    assert!(frames.next().await.is_none());
+}
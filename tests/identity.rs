@@ -0,0 +1,37 @@
+use wasm_bindgen_test::wasm_bindgen_test;
+
+mod mock;
+
+use webusb_web::*;
+
+#[wasm_bindgen_test]
+async fn device_by_identity() {
+    mock::install_usb();
+    mock::add_device("{ vendorId: 1, productId: 2, serialNumber: 'a' }");
+    let dev = mock::add_device("{ vendorId: 1, productId: 2, serialNumber: 'b' }");
+
+    let identity = dev.identity();
+    assert_eq!(identity.serial_number.as_deref(), Some("b"));
+
+    let usb = Usb::new().unwrap();
+    assert_eq!(usb.device_by_identity(&identity).await, Some(dev.clone()));
+
+    mock::remove_device(&dev);
+    assert_eq!(usb.device_by_identity(&identity).await, None);
+}
+
+#[cfg(feature = "serde")]
+#[wasm_bindgen_test]
+fn serde_round_trip() {
+    let dev = mock::device("{ vendorId: 0x1234, productId: 0x5678, serialNumber: 'abc' }");
+    let identity = dev.identity();
+    let json = serde_json::to_string(&identity).unwrap();
+    assert_eq!(serde_json::from_str::<UsbDeviceIdentity>(&json).unwrap(), identity);
+
+    let filter = UsbDeviceFilter::new().with_vendor_id(0x1234).with_serial_number("abc");
+    let json = serde_json::to_string(&filter).unwrap();
+    let restored: UsbDeviceFilter = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.vendor_id, Some(0x1234));
+    assert_eq!(restored.serial_number.as_deref(), Some("abc"));
+    assert_eq!(restored.product_id, None);
+}
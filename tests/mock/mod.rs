@@ -0,0 +1,166 @@
+//! Mock USB devices backed by plain JavaScript objects.
+#![allow(dead_code)]
+
+use wasm_bindgen::JsCast;
+use webusb_web::UsbDevice;
+
+/// Creates a USB device backed by the JavaScript object literal `js`.
+///
+/// The object only needs to provide the properties and methods used by the test.
+pub fn device(js: &str) -> UsbDevice {
+    let obj = js_sys::eval(&format!("({js})")).expect("invalid mock device");
+    UsbDevice::from(obj.unchecked_into::<web_sys::UsbDevice>())
+}
+
+/// JavaScript object literal members implementing opening, closing and interface claiming
+/// of a mock device.
+pub const OPEN_CLOSE: &str = "
+    opened: false,
+    open() { this.opened = true; return Promise.resolve(); },
+    close() { this.opened = false; return Promise.resolve(); },
+    claimInterface(iface) { return Promise.resolve(); },
+    releaseInterface(iface) { return Promise.resolve(); },
+";
+
+/// Installs stand-ins for the WebUSB transfer result and descriptor classes into the global scope.
+pub fn install_classes() {
+    js_sys::eval(
+        "
+        globalThis.USBInTransferResult ??= class USBInTransferResult {
+            constructor(status, data) { this.status = status; this.data = data; }
+        };
+        globalThis.USBIsochronousInTransferResult ??= class USBIsochronousInTransferResult {
+            constructor(data, packets) { this.data = data; this.packets = packets; }
+        };
+        globalThis.USBIsochronousInTransferPacket ??= class USBIsochronousInTransferPacket {
+            constructor(status, data) { this.status = status; this.data = data; }
+        };
+        globalThis.USBIsochronousOutTransferResult ??= class USBIsochronousOutTransferResult {
+            constructor(packets) { this.packets = packets; }
+        };
+        globalThis.USBIsochronousOutTransferPacket ??= class USBIsochronousOutTransferPacket {
+            constructor(status, bytesWritten = 0) { this.status = status; this.bytesWritten = bytesWritten; }
+        };
+        globalThis.USBOutTransferResult ??= class USBOutTransferResult {
+            constructor(status, bytesWritten = 0) { this.status = status; this.bytesWritten = bytesWritten; }
+        };
+        globalThis.USBConfiguration ??= class USBConfiguration {
+            constructor(configurationValue, interfaces, configurationName = null) {
+                Object.assign(this, { configurationValue, interfaces, configurationName });
+            }
+        };
+        globalThis.USBInterface ??= class USBInterface {
+            constructor(interfaceNumber, alternates, claimed = false) {
+                Object.assign(this, { interfaceNumber, alternates, alternate: alternates[0], claimed });
+            }
+        };
+        globalThis.USBAlternateInterface ??= class USBAlternateInterface {
+            constructor(alternateSetting, [interfaceClass, interfaceSubclass, interfaceProtocol], endpoints,
+                        interfaceName = null) {
+                Object.assign(this, {
+                    alternateSetting, interfaceClass, interfaceSubclass, interfaceProtocol, endpoints, interfaceName
+                });
+            }
+        };
+        globalThis.USBEndpoint ??= class USBEndpoint {
+            constructor(endpointNumber, direction, type, packetSize) {
+                Object.assign(this, { endpointNumber, direction, type, packetSize });
+            }
+        };
+        ",
+    )
+    .expect("cannot install mock classes");
+}
+
+/// JavaScript object literal members describing a configured device with a bulk IN/OUT pair
+/// on claimed interface 0 and an interrupt IN endpoint on unclaimed interface 1.
+pub const CONFIGURED: &str = "
+    get configuration() { return this.configurations[0]; },
+    configurations: [
+        new USBConfiguration(1, [
+            new USBInterface(0, [
+                new USBAlternateInterface(0, [0xff, 0, 0], [
+                    new USBEndpoint(1, 'in', 'bulk', 512),
+                    new USBEndpoint(2, 'out', 'bulk', 512),
+                ]),
+            ], true),
+            new USBInterface(1, [
+                new USBAlternateInterface(0, [0x03, 0, 0], [new USBEndpoint(3, 'in', 'interrupt', 8)]),
+            ]),
+        ]),
+    ],
+";
+
+/// Installs a mock WebUSB API into the global scope, so that [`webusb_web::Usb::new`] succeeds.
+///
+/// The paired devices returned by `getDevices` are taken from `navigator.usb.devices`.
+/// `requestDevice` selects the first of them, ignoring the filters.
+/// Use [`add_device`] and [`remove_device`] to change them.
+pub fn install_usb() {
+    js_sys::eval(
+        "
+        if (!globalThis.Window) {
+            globalThis.Window = class Window {};
+            Object.setPrototypeOf(globalThis, Window.prototype);
+        }
+        globalThis.USBDevice ??= class USBDevice {
+            constructor(props) {
+                Object.assign(this, {
+                    vendorId: 0, productId: 0, deviceClass: 0, deviceSubclass: 0, deviceProtocol: 0,
+                    configuration: null, configurations: [],
+                }, props);
+            }
+        };
+        const usb = new EventTarget();
+        usb.devices = [];
+        usb.getDevices = function() { return Promise.resolve([...this.devices]); };
+        usb.requestDevice = function(options) {
+            return this.devices.length ? Promise.resolve(this.devices[0])
+                                       : Promise.reject(new DOMException('No device selected.', 'NotFoundError'));
+        };
+        Object.defineProperty(globalThis, 'navigator', { value: { usb }, configurable: true, writable: true });
+        ",
+    )
+    .expect("cannot install mock WebUSB API");
+}
+
+/// Adds a paired device described by the JavaScript object literal `js` to the mock WebUSB API.
+pub fn add_device(js: &str) -> UsbDevice {
+    let obj = js_sys::eval(&format!(
+        "(() => {{ const dev = new USBDevice({js}); navigator.usb.devices.push(dev); return dev; }})()"
+    ))
+    .expect("invalid mock device");
+    UsbDevice::from(obj.unchecked_into::<web_sys::UsbDevice>())
+}
+
+/// Removes a paired device from the mock WebUSB API.
+pub fn remove_device(dev: &UsbDevice) {
+    let usb = js_sys::eval("navigator.usb").expect("mock WebUSB API not installed");
+    let devices = js_sys::Reflect::get(&usb, &"devices".into()).unwrap().unchecked_into::<js_sys::Array>();
+    let device: &wasm_bindgen::JsValue = dev.as_ref().as_ref();
+    let kept = devices.filter(&mut |other, _, _| &other != device);
+    js_sys::Reflect::set(&usb, &"devices".into(), &kept).unwrap();
+}
+
+/// Dispatches a connection event of the specified type (`connect` or `disconnect`)
+/// for a device on the mock WebUSB API.
+///
+/// Like a browser, a disconnected device is marked as closed.
+pub fn dispatch(event_type: &str, dev: &UsbDevice) {
+    if event_type == "disconnect" {
+        js_sys::Reflect::set(dev.as_ref(), &"opened".into(), &false.into()).unwrap();
+    }
+    let event = web_sys::Event::new(event_type).unwrap();
+    js_sys::Reflect::set(&event, &"device".into(), dev.as_ref()).unwrap();
+    let usb = js_sys::eval("navigator.usb").expect("mock WebUSB API not installed");
+    usb.unchecked_into::<web_sys::EventTarget>().dispatch_event(&event).unwrap();
+}
+
+/// Lets pending promises and spawned tasks run by waiting for a timer of zero duration.
+pub async fn idle() {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        let set_timeout = js_sys::eval("setTimeout").unwrap().unchecked_into::<js_sys::Function>();
+        set_timeout.call2(&wasm_bindgen::JsValue::NULL, &resolve, &0.into()).unwrap();
+    });
+    wasm_bindgen_futures::JsFuture::from(promise).await.unwrap();
+}
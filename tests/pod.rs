@@ -0,0 +1,40 @@
+#![cfg(feature = "bytemuck")]
+
+use bytemuck::{Pod, Zeroable};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+mod mock;
+
+use webusb_web::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+#[repr(C)]
+struct Config {
+    version: u16,
+    flags: u8,
+    channels: u8,
+    baud_rate: u32,
+}
+
+#[wasm_bindgen_test]
+async fn control_read_pod() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            controlTransferIn(setup, len) {{
+                const data = new Uint8Array([0x02, 0x01, 0x80, 0x04, 0x00, 0xc2, 0x01, 0x00]).slice(0, setup.value);
+                return Promise.resolve(new USBInTransferResult('ok', new DataView(data.buffer)));
+            }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let request = UsbControlRequest::new(UsbRequestType::Vendor, UsbRecipient::Device, 1, 8, 0);
+    let config: Config = open.control_read_pod(&request).await.unwrap();
+    assert_eq!(config, Config { version: 0x0102, flags: 0x80, channels: 4, baud_rate: 115200 });
+
+    let request = UsbControlRequest::new(UsbRequestType::Vendor, UsbRecipient::Device, 1, 6, 0);
+    let err = open.control_read_pod::<Config>(&request).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
@@ -1,5 +1,5 @@
 use futures_util::StreamExt;
-use tokio::sync::oneshot;
+use std::pin::pin;
 use wasm_bindgen_futures::spawn_local;
 use wasm_bindgen_test::wasm_bindgen_test;
 
@@ -12,7 +12,8 @@ use webusb_web::*;
 async fn test() {
     log!("Getting WebUSB API");
     let usb = Usb::new().expect_log("cannot get WebUSB API");
-    log!("Obtained WebUSB API");
+    log!("Obtained WebUSB API in context {:?}", usb.context());
+    assert_ne!(usb.context(), UsbContext::Unknown);
 
     let mut filter = UsbDeviceFilter::new();
     filter.vendor_id = Some(0x06);
@@ -20,23 +21,9 @@ async fn test() {
 
     log!("Starting event stream");
     let mut events = usb.events();
-    let (disconnected_tx, disconnected_rx) = oneshot::channel();
     spawn_local(async move {
-        let mut disconnected_tx = Some(disconnected_tx);
         while let Some(event) = events.next().await {
             log!("WebUSB event: {event:?}");
-
-            match &event {
-                UsbEvent::Disconnected(dev)
-                    if dev.vendor_id() == filter.vendor_id.unwrap_log()
-                        && dev.product_id() == filter.product_id.unwrap_log() =>
-                {
-                    if let Some(disconnected_tx) = disconnected_tx.take() {
-                        disconnected_tx.send(()).unwrap();
-                    }
-                }
-                _ => (),
-            }
         }
     });
     log!("Event stream started");
@@ -95,13 +82,38 @@ async fn test() {
     assert!(found, "device not enumerated after paired");
     log!("Enumeration complete");
 
+    log!("Checking that paired device is replayed as connected event");
+    {
+        let mut events = pin!(usb.events_with_current());
+        let mut found = false;
+        for _ in 0..usb.devices().await.len() {
+            match events.next().await.unwrap_log() {
+                UsbEvent::Connected(device) if device == dev => found = true,
+                UsbEvent::Connected(_) => (),
+                other => panic!("unexpected initial event: {other:?}"),
+            }
+        }
+        assert!(found, "paired device not replayed as connected event");
+    }
+
     let cfg = dev.configuration().expect_log("device has no active configuration");
     log!("Active device configuration: {cfg:?}");
 
+    let mut n_endpoints = 0;
+    for cfg in dev.configurations() {
+        for iface in &cfg.interfaces {
+            for alt in &iface.alternates {
+                n_endpoints += alt.endpoints.len();
+            }
+        }
+    }
+    assert_eq!(dev.iter_endpoints().count(), n_endpoints);
+    log!("Device has {n_endpoints} endpoints");
+
     let iface = cfg.interfaces.first().unwrap_log();
     let alt = &iface.alternate;
     assert_eq!(alt.alternate_setting, 0);
-    assert_eq!(alt.interface_class, 255);
+    assert_eq!(alt.interface_class, class_codes::VENDOR_SPECIFIC);
     assert_eq!(alt.interface_subclass, 1);
     assert_eq!(alt.interface_protocol, 2);
 
@@ -127,6 +139,7 @@ async fn test() {
     let in_ep = in_ep.unwrap_log();
     let out_packet_size = out_packet_size.unwrap_log();
     let out_ep = out_ep.unwrap_log();
+    assert_eq!(alt.duplex_pair(), Some((in_ep, out_ep)));
 
     log!("Opening device");
     let open = dev.open().await.expect_log("device failed to open");
@@ -206,19 +219,51 @@ async fn test() {
     open.claim_interface(0).await.expect_log("failed to claim interface");
     log!("Interface claimed");
 
-    log!("Terminating USB gadget");
-    let control = UsbControlRequest::new(
-        UsbRequestType::Class,
-        UsbRecipient::Interface,
-        0xff,
-        0,
-        iface.interface_number.into(),
-    );
-    open.control_transfer_out(&control, &[]).await.unwrap_log();
+    log!("Reading into buffer of packet size");
+    let mut buf = vec![0; in_packet_size as usize];
+    let n = open.transfer_in_copy_to(in_ep, &mut buf).await.unwrap_log();
+    assert_eq!(n, buf.len());
+    assert!(buf.iter().all(|x| *x == buf[0]));
+
+    log!("Reading with pipelined reader");
+    {
+        let mut reader = pin!(open.pipelined_reader(in_ep, in_packet_size, 4));
+        let mut b = None;
+        for _ in 0..64 {
+            let data = reader.next().await.unwrap_log().unwrap_log();
+            assert_eq!(data.len(), in_packet_size as usize);
+
+            let b = b.get_or_insert(data[0]);
+            assert!(data.iter().all(|x| x == b), "pipelined reader returned data out of order");
+            *b = b.wrapping_add(1);
+        }
+    }
+    log!("Pipelined reader data is in order");
+
+    log!("Terminating USB gadget while reading");
+    let disconnected = open.disconnected(&usb);
+    let read_task = open.with_disconnect(&usb, async {
+        loop {
+            open.transfer_in(in_ep, in_packet_size).await?;
+        }
+    });
+    let terminate_task = async {
+        let control = UsbControlRequest::new(
+            UsbRequestType::Class,
+            UsbRecipient::Interface,
+            0xff,
+            0,
+            iface.interface_number.into(),
+        );
+        open.control_transfer_out(&control, &[]).await.unwrap_log();
+    };
+    let (res, ()): (Result<()>, ()) = tokio::join!(read_task, terminate_task);
+    log!("Read result after termination: {res:?}");
+    assert!(res.is_err(), "read succeeded after disconnect");
 
     log!("Waiting for disconnect event");
-    disconnected_rx.await.unwrap_log();
+    disconnected.await;
 
     log!("Device disconnected");
-    dev.forget().await;
+    dev.forget().await.unwrap_log();
 }
@@ -0,0 +1,732 @@
+use futures_util::StreamExt;
+use std::{cell::RefCell, pin::pin, rc::Rc, time::Duration};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+mod mock;
+
+use webusb_web::*;
+
+#[wasm_bindgen_test]
+async fn security_error_hint() {
+    let dev = mock::device(&format!(
+        "{{ {} transferIn() {{ return Promise.reject(new DOMException('Access denied.', 'SecurityError')); }} }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let err = open.transfer_in(1, 64).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Security);
+    assert!(err.msg().starts_with("Access denied."));
+    assert!(err.msg().contains("protected class"));
+}
+
+#[wasm_bindgen_test]
+async fn data_view() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} transferIn() {{
+            return Promise.resolve(new USBInTransferResult('ok', new DataView(new Uint8Array([0xaa, 0x78, 0x56, 0x34, 0x12]).buffer, 1)));
+        }} }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let view = open.transfer_in_data_view(1, 64).await.unwrap();
+    assert_eq!(view.byte_length(), 4);
+    assert_eq!(view.get_uint32_endian(0, true), 0x12345678);
+}
+
+#[wasm_bindgen_test]
+async fn short_packet() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} transferIn(ep, len) {{
+            return Promise.resolve(new USBInTransferResult('ok', new DataView(new Uint8Array(Math.min(len, 10)).buffer)));
+        }} }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let res = open.transfer_in_detailed(1, 64).await.unwrap();
+    assert_eq!(res.data.len(), 10);
+    assert!(res.is_short);
+
+    let res = open.transfer_in_detailed(1, 10).await.unwrap();
+    assert_eq!(res.data.len(), 10);
+    assert!(!res.is_short);
+}
+
+#[wasm_bindgen_test]
+async fn length_overflow() {
+    let dev = mock::device(&format!(
+        "{{ {} transferIn() {{ throw new Error('must not be called'); }}, controlTransferIn() {{ throw new Error('must not be called'); }} }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let control = UsbControlRequest::new(UsbRequestType::Vendor, UsbRecipient::Device, 1, 0, 0);
+    let err = open.control_transfer_in_usize(&control, usize::from(u16::MAX) + 1).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidAccess);
+
+    if let Ok(len) = usize::try_from(u64::from(u32::MAX) + 1) {
+        let err = open.transfer_in_usize(1, len).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidAccess);
+    }
+}
+
+#[wasm_bindgen_test]
+async fn transfer_in_on() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} {} transferIn(ep, len) {{
+            return Promise.resolve(new USBInTransferResult('ok', new DataView(new Uint8Array(len).buffer)));
+        }} }}",
+        mock::OPEN_CLOSE,
+        mock::CONFIGURED
+    ));
+    let open = dev.open().await.unwrap();
+
+    assert_eq!(open.transfer_in_on(0, 1, 8).await.unwrap().len(), 8);
+    assert_eq!(open.transfer_in_on(1, 1, 8).await.unwrap_err().kind(), ErrorKind::InvalidAccess);
+    assert_eq!(open.transfer_in_on(0, 2, 8).await.unwrap_err().kind(), ErrorKind::InvalidAccess);
+}
+
+#[wasm_bindgen_test]
+async fn flush_in() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            halted: true,
+            stale: [64, 64, 10],
+            transfers: 0,
+            clearHalt(dir, ep) {{ this.halted = false; return Promise.resolve(); }},
+            transferIn(ep, len) {{
+                this.transfers++;
+                if (this.halted) return Promise.resolve(new USBInTransferResult('stall', undefined));
+                if (this.stale.length == 0) return new Promise(resolve => {{ this.waiting = resolve; }});
+                const n = Math.min(len, this.stale.shift());
+                return Promise.resolve(new USBInTransferResult('ok', new DataView(new Uint8Array(n).buffer)));
+            }},
+            send(data) {{ this.waiting(new USBInTransferResult('ok', new DataView(new Uint8Array(data).buffer))); }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let transfers = || js_sys::Reflect::get(dev.as_ref(), &"transfers".into()).unwrap().as_f64().unwrap();
+    let open = dev.open().await.unwrap();
+
+    let discarded = open.flush_in(1, 64, Duration::from_millis(10)).await.unwrap();
+    assert_eq!(discarded, 138);
+    assert_eq!(transfers(), 4.);
+
+    // The first packet after the flush is received by the transfer left pending.
+    let send: js_sys::Function = js_sys::Reflect::get(dev.as_ref(), &"send".into()).unwrap().unchecked_into();
+    send.call1(dev.as_ref(), &js_sys::Array::of3(&1.into(), &2.into(), &3.into())).unwrap();
+    assert_eq!(open.transfer_in(1, 64).await.unwrap(), [1, 2, 3]);
+    assert_eq!(transfers(), 4., "pending transfer must be reused");
+}
+
+#[wasm_bindgen_test]
+async fn flush_in_short_reads() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            transfers: 0,
+            clearHalt(dir, ep) {{ return Promise.resolve(); }},
+            transferIn(ep, len) {{
+                this.transfers++;
+                return new Promise(resolve => {{ this.waiting = resolve; }});
+            }},
+            send(data) {{ this.waiting(new USBInTransferResult('ok', new DataView(new Uint8Array(data).buffer))); }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let transfers = || js_sys::Reflect::get(dev.as_ref(), &"transfers".into()).unwrap().as_f64().unwrap();
+    let open = dev.open().await.unwrap();
+    let lengths = Rc::new(RefCell::new(Vec::new()));
+    let hook_lengths = lengths.clone();
+    open.set_transfer_hook(move |log| hook_lengths.borrow_mut().push((log.length, log.result.clone().unwrap())));
+
+    assert_eq!(open.flush_in(1, 64, Duration::from_millis(10)).await.unwrap(), 0);
+    let send: js_sys::Function = js_sys::Reflect::get(dev.as_ref(), &"send".into()).unwrap().unchecked_into();
+    let data: js_sys::Array = (1..=10).map(wasm_bindgen::JsValue::from).collect();
+    send.call1(dev.as_ref(), &data).unwrap();
+
+    // Data exceeding the requested length is returned by the following reads.
+    let res = open.transfer_in_detailed(1, 4).await.unwrap();
+    assert_eq!(res.data, [1, 2, 3, 4]);
+    assert!(!res.is_short);
+    let mut buf = [0; 4];
+    assert_eq!(open.transfer_in_copy_to(1, &mut buf).await.unwrap(), 4);
+    assert_eq!(buf, [5, 6, 7, 8]);
+    let rest = open.read_exact_deadline(1, 2, Deadline::after(Duration::from_secs(1))).await.unwrap();
+    assert_eq!(rest, [9, 10]);
+
+    assert_eq!(transfers(), 1., "no new transfer must be started while data is carried over");
+    assert_eq!(*lengths.borrow(), [(4, 4), (4, 4), (2, 2)]);
+}
+
+#[wasm_bindgen_test]
+async fn transfer_hook() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            transferIn(ep, len) {{
+                if (ep == 3) return Promise.resolve(new USBInTransferResult('stall', undefined));
+                return Promise.resolve(new USBInTransferResult('ok', new DataView(new Uint8Array(4).buffer)));
+            }},
+            transferOut(ep, data) {{ return Promise.resolve(new USBOutTransferResult('ok', data.length)); }},
+            controlTransferOut(setup, data) {{ return Promise.resolve(new USBOutTransferResult('ok', data.length)); }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let logs = Rc::new(RefCell::new(Vec::new()));
+    let hook_logs = logs.clone();
+    open.set_transfer_hook(move |log| hook_logs.borrow_mut().push(log.clone()));
+
+    open.transfer_in(1, 64).await.unwrap();
+    open.transfer_out(2, &[1, 2, 3]).await.unwrap();
+    open.transfer_in(3, 8).await.unwrap_err();
+    let control = UsbControlRequest::new(UsbRequestType::Vendor, UsbRecipient::Device, 1, 0, 0);
+    open.control_transfer_out(&control, &[1, 2]).await.unwrap();
+
+    let logs = logs.borrow();
+    assert_eq!(logs.len(), 4);
+    assert_eq!((logs[0].direction, logs[0].endpoint, logs[0].length), (UsbDirection::In, 1, 64));
+    assert_eq!(logs[0].result, Ok(4));
+    assert_eq!((logs[1].direction, logs[1].endpoint, logs[1].length), (UsbDirection::Out, 2, 3));
+    assert_eq!(logs[1].result, Ok(3));
+    assert_eq!(logs[2].result.as_ref().unwrap_err().kind(), ErrorKind::Stall);
+    assert_eq!((logs[3].direction, logs[3].endpoint, logs[3].result.clone()), (UsbDirection::Out, 0, Ok(2)));
+}
+
+#[wasm_bindgen_test]
+async fn malformed_result() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            transferIn(ep, len) {{
+                return Promise.resolve(ep == 1 ? {{ status: 'ok' }} : new USBInTransferResult('ok', undefined));
+            }},
+            transferOut(ep, data) {{ return Promise.resolve(new USBOutTransferResult('exploded', 0)); }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    assert_eq!(open.transfer_in(1, 8).await.unwrap_err().kind(), ErrorKind::Other);
+    assert_eq!(open.transfer_in(2, 8).await.unwrap_err().kind(), ErrorKind::Other);
+    assert_eq!(open.transfer_out(1, &[0]).await.unwrap_err().kind(), ErrorKind::Other);
+}
+
+#[wasm_bindgen_test]
+async fn isochronous_in_stream() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            transfers: 0,
+            isochronousTransferIn(ep, lens) {{
+                const n = ++this.transfers;
+                const buffer = new Uint8Array(lens.reduce((a, b) => a + b, 0)).fill(n).buffer;
+                let offset = 0;
+                const packets = lens.map((len) => {{
+                    const packet = new USBIsochronousInTransferPacket('ok', new DataView(buffer, offset, len));
+                    offset += len;
+                    return packet;
+                }});
+                return Promise.resolve(new USBIsochronousInTransferResult(new DataView(buffer), packets));
+            }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    {
+        let mut stream = pin!(open.isochronous_in_stream(1, vec![4, 2]));
+        for n in 1..=3 {
+            let packets = stream.next().await.unwrap().unwrap();
+            assert_eq!(packets.len(), 2);
+            assert_eq!(packets[0].as_ref().unwrap(), &[n; 4]);
+            assert_eq!(packets[1].as_ref().unwrap(), &[n; 2]);
+        }
+    }
+
+    let transfers = js_sys::Reflect::get(dev.as_ref(), &"transfers".into()).unwrap();
+    assert_eq!(transfers.as_f64(), Some(3.0));
+}
+
+#[wasm_bindgen_test]
+async fn isochronous_in_stream_ends_on_error() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            transfers: 0,
+            isochronousTransferIn(ep, lens) {{
+                this.transfers++;
+                return Promise.reject(new DOMException('The device was disconnected.', 'NotFoundError'));
+            }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let mut stream = pin!(open.isochronous_in_stream(1, vec![4]));
+    assert!(stream.next().await.unwrap().is_err());
+    assert!(stream.next().await.is_none(), "stream must end after a transfer error");
+
+    let transfers = js_sys::Reflect::get(dev.as_ref(), &"transfers".into()).unwrap();
+    assert_eq!(transfers.as_f64(), Some(1.0));
+}
+
+#[wasm_bindgen_test]
+async fn endpoint_address() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} transferIn(ep, len) {{
+            return Promise.resolve(new USBInTransferResult('ok', new DataView(new Uint8Array([ep]).buffer)));
+        }},
+        transferOut(ep, data) {{ return Promise.resolve(new USBOutTransferResult('ok', ep)); }} }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    assert_eq!(open.transfer_in_addr(0x81, 64).await.unwrap(), [1]);
+    assert_eq!(open.transfer_out_addr(0x02, &[0; 4]).await.unwrap(), 2);
+
+    let err = open.transfer_in_addr(0x01, 64).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidAccess);
+    let err = open.transfer_out_addr(0x82, &[0; 4]).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidAccess);
+}
+
+#[wasm_bindgen_test]
+async fn read_exact_deadline() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} transferIn(ep, len) {{
+            if (ep != 1) return new Promise(() => {{}});
+            return Promise.resolve(new USBInTransferResult('ok', new DataView(new Uint8Array(Math.min(len, 3)).fill(len).buffer)));
+        }} }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let data = open.read_exact_deadline(1, 8, Deadline::after(Duration::from_secs(10))).await.unwrap();
+    assert_eq!(data, [8, 8, 8, 5, 5, 5, 2, 2]);
+
+    let err = open.read_exact_deadline(2, 8, Deadline::after(Duration::from_millis(20))).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+
+    let deadline = Deadline::after(Duration::ZERO);
+    assert!(deadline.has_passed());
+    let err = open.read_exact_deadline(1, 8, deadline).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+}
+
+#[wasm_bindgen_test]
+async fn read_exact_deadline_retry() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            chunks: [[1, 2, 3]],
+            transferIn(ep, len) {{
+                const chunk = this.chunks.shift();
+                if (chunk === undefined) return new Promise(resolve => {{ this.waiting = resolve; }});
+                return Promise.resolve(new USBInTransferResult('ok', new DataView(new Uint8Array(chunk).buffer)));
+            }},
+            send(data) {{ this.waiting(new USBInTransferResult('ok', new DataView(new Uint8Array(data).buffer))); }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let err = open.read_exact_deadline(1, 6, Deadline::after(Duration::from_millis(20))).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+
+    // The retry receives the partial data and the data of the pending transfer.
+    let send: js_sys::Function = js_sys::Reflect::get(dev.as_ref(), &"send".into()).unwrap().unchecked_into();
+    send.call1(dev.as_ref(), &js_sys::Array::of3(&4.into(), &5.into(), &6.into())).unwrap();
+    let data = open.read_exact_deadline(1, 6, Deadline::after(Duration::from_secs(10))).await.unwrap();
+    assert_eq!(data, [1, 2, 3, 4, 5, 6]);
+}
+
+#[wasm_bindgen_test]
+async fn default_read_len() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} {} transferIn(ep, len) {{
+            return Promise.resolve(new USBInTransferResult('ok', new DataView(new Uint8Array(len).buffer)));
+        }} }}",
+        mock::OPEN_CLOSE,
+        mock::CONFIGURED
+    ));
+    let open = dev.open().await.unwrap();
+
+    assert_eq!(open.transfer_in_default(1).await.unwrap().len(), 512);
+    let err = open.transfer_in_default(3).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidAccess);
+
+    open.set_default_read_len(2048);
+    assert_eq!(open.transfer_in_default(1).await.unwrap().len(), 2048);
+    assert_eq!(open.transfer_in_default(3).await.unwrap().len(), 2048);
+}
+
+#[wasm_bindgen_test]
+async fn timed() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} transferIn(ep, len) {{
+            return new Promise(resolve => setTimeout(
+                () => resolve(new USBInTransferResult('ok', new DataView(new Uint8Array(len).buffer))), 20));
+        }},
+        transferOut(ep, data) {{ return Promise.resolve(new USBOutTransferResult('ok', data.byteLength)); }} }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let (data, elapsed) = open.transfer_in_timed(1, 8).await.unwrap();
+    assert_eq!(data.len(), 8);
+    assert!(elapsed >= Duration::from_millis(10), "{elapsed:?}");
+
+    let (written, elapsed) = open.transfer_out_timed(2, &[0; 4]).await.unwrap();
+    assert_eq!(written, 4);
+    assert!(elapsed < Duration::from_secs(10), "{elapsed:?}");
+}
+
+#[wasm_bindgen_test]
+async fn transfer_in_or_timeout() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            transfers: 0,
+            transferIn(ep, len) {{
+                if (ep != 1) {{
+                    this.transfers++;
+                    return new Promise(resolve => {{ this.waiting = resolve; }});
+                }}
+                return Promise.resolve(new USBInTransferResult('ok', new DataView(new Uint8Array([7]).buffer)));
+            }},
+            send(data) {{ this.waiting(new USBInTransferResult('ok', new DataView(new Uint8Array(data).buffer))); }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let transfers = || js_sys::Reflect::get(dev.as_ref(), &"transfers".into()).unwrap().as_f64().unwrap();
+    let open = dev.open().await.unwrap();
+
+    let timeout = Duration::from_millis(10);
+    assert_eq!(open.transfer_in_or_timeout(1, 8, timeout).await.unwrap(), Some(vec![7]));
+    assert_eq!(open.transfer_in_or_timeout(3, 8, timeout).await.unwrap(), None);
+    assert_eq!(open.transfer_in_or_timeout(3, 8, timeout).await.unwrap(), None);
+    assert_eq!(transfers(), 1., "timed out transfer must be reused");
+
+    // Data sent after a timeout is received by the next call.
+    let send: js_sys::Function = js_sys::Reflect::get(dev.as_ref(), &"send".into()).unwrap().unchecked_into();
+    send.call1(dev.as_ref(), &js_sys::Array::of2(&5.into(), &6.into())).unwrap();
+    assert_eq!(open.transfer_in_or_timeout(3, 8, timeout).await.unwrap(), Some(vec![5, 6]));
+    assert_eq!(transfers(), 1.);
+}
+
+#[wasm_bindgen_test]
+async fn isochronous_packet_data() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            isochronousTransferIn(ep, lens) {{
+                // The second packet is short, leaving a gap in the transfer buffer.
+                const buffer = new Uint8Array([1, 2, 3, 4, 5, 0, 0, 0, 9, 10]).buffer;
+                const packets = [
+                    new USBIsochronousInTransferPacket('ok', new DataView(buffer, 0, 4)),
+                    new USBIsochronousInTransferPacket('ok', new DataView(buffer, 4, 1)),
+                    new USBIsochronousInTransferPacket('ok', new DataView(buffer, 8, 2)),
+                ];
+                return Promise.resolve(new USBIsochronousInTransferResult(new DataView(buffer), packets));
+            }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let packets = open.isochronous_transfer_in(1, [4, 4, 2]).await.unwrap();
+    let packets: Vec<_> = packets.into_iter().map(Result::unwrap).collect();
+    assert_eq!(packets, [vec![1, 2, 3, 4], vec![5], vec![9, 10]]);
+}
+
+#[wasm_bindgen_test]
+async fn isochronous_packet_errors() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            isochronousTransferIn(ep, lens) {{
+                const packets = lens.map((len, i) => i == 2
+                    ? new USBIsochronousInTransferPacket('babble', undefined)
+                    : new USBIsochronousInTransferPacket('ok', new DataView(new Uint8Array(len).buffer)));
+                return Promise.resolve(new USBIsochronousInTransferResult(undefined, packets));
+            }},
+            isochronousTransferOut(ep, data, lens) {{
+                const packets = lens.map((len, i) => i == 2
+                    ? new USBIsochronousOutTransferPacket('stall')
+                    : new USBIsochronousOutTransferPacket('ok', len));
+                return Promise.resolve(new USBIsochronousOutTransferResult(packets));
+            }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let packets = open.isochronous_transfer_in(1, [8; 4]).await.unwrap();
+    assert_eq!(packets.len(), 4);
+    assert!(packets[1].is_ok() && packets[3].is_ok());
+    let err = packets[2].as_ref().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Babble);
+    assert!(err.msg().contains("packet 2"), "{}", err.msg());
+
+    let packets = open.isochronous_transfer_out(2, [&[0; 8][..]; 4]).await.unwrap();
+    assert_eq!(packets.len(), 4);
+    let err = packets[2].as_ref().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Stall);
+    assert!(err.msg().contains("packet 2"), "{}", err.msg());
+}
+
+#[wasm_bindgen_test]
+async fn out_rate_limit() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} times: [], transferOut(ep, data) {{
+            this.times.push(performance.now());
+            return Promise.resolve(new USBOutTransferResult('ok', data.byteLength));
+        }} }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    open.set_out_rate_limit(1000);
+    for _ in 0..3 {
+        open.transfer_out(1, &[0; 20]).await.unwrap();
+    }
+    open.set_out_rate_limit(0);
+    open.transfer_out(1, &[0; 20]).await.unwrap();
+
+    let times = js_sys::Reflect::get(dev.as_ref(), &"times".into()).unwrap();
+    let times: Vec<f64> = js_sys::Array::from(&times).iter().map(|t| t.as_f64().unwrap()).collect();
+    // 20 bytes at 1000 bytes per second take 20 ms; allow for timer inaccuracy.
+    assert!(times[1] - times[0] >= 15., "{times:?}");
+    assert!(times[2] - times[1] >= 15., "{times:?}");
+    assert!(times[3] - times[2] < 15., "{times:?}");
+}
+
+#[wasm_bindgen_test]
+async fn out_rate_limit_cancelled() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} times: [], transferOut(ep, data) {{
+            this.times.push(performance.now());
+            return Promise.resolve(new USBOutTransferResult('ok', data.byteLength));
+        }} }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    open.set_out_rate_limit(200);
+    open.transfer_out(1, &[0; 20]).await.unwrap();
+    for _ in 0..3 {
+        let mut cancelled = Box::pin(open.transfer_out(1, &[0; 20]));
+        assert!(futures_util::poll!(cancelled.as_mut()).is_pending());
+    }
+    open.transfer_out(1, &[0; 20]).await.unwrap();
+
+    let times = js_sys::Reflect::get(dev.as_ref(), &"times".into()).unwrap();
+    let times: Vec<f64> = js_sys::Array::from(&times).iter().map(|t| t.as_f64().unwrap()).collect();
+    // 20 bytes at 200 bytes per second take 100 ms; cancelled transfers must not add to this.
+    assert_eq!(times.len(), 2, "{times:?}");
+    assert!(times[1] - times[0] >= 80., "{times:?}");
+    assert!(times[1] - times[0] < 250., "{times:?}");
+}
+
+#[wasm_bindgen_test]
+async fn duplex() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {}
+            transferOut(ep, data) {{
+                return new Promise(resolve => setTimeout(() => {{
+                    this.command = new Uint8Array(data)[0];
+                    resolve(new USBOutTransferResult('ok', data.byteLength));
+                }}, 10));
+            }},
+            transferIn(ep, len) {{
+                const data = new Uint8Array([this.command + 0x80]);
+                return new Promise(resolve => setTimeout(
+                    () => resolve(new USBInTransferResult('ok', new DataView(data.buffer))), 10));
+            }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+    let duplex = open.duplex(1, 2, 64);
+
+    let (res1, res2) = tokio::join!(duplex.request(&[1]), duplex.request(&[2]));
+    assert_eq!(res1.unwrap(), [0x81]);
+    assert_eq!(res2.unwrap(), [0x82]);
+}
+
+#[wasm_bindgen_test]
+async fn zero_length_packet() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} {} lengths: [], transferOut(ep, data) {{
+            this.lengths.push(data.byteLength);
+            return Promise.resolve(new USBOutTransferResult('ok', data.byteLength));
+        }} }}",
+        mock::OPEN_CLOSE,
+        mock::CONFIGURED
+    ));
+    let open = dev.open().await.unwrap();
+
+    open.transfer_out(2, &[0; 512]).await.unwrap();
+    open.send_zlp(2).await.unwrap();
+    assert_eq!(open.transfer_out_with_zlp(2, &[0; 1024]).await.unwrap(), 1024);
+    assert_eq!(open.transfer_out_with_zlp(2, &[0; 100]).await.unwrap(), 100);
+
+    let lengths = js_sys::Reflect::get(dev.as_ref(), &"lengths".into()).unwrap();
+    let lengths: Vec<f64> = js_sys::Array::from(&lengths).iter().map(|n| n.as_f64().unwrap()).collect();
+    assert_eq!(lengths, [512., 0., 1024., 0., 100.]);
+
+    let err = open.transfer_out_with_zlp(3, &[0; 8]).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidAccess);
+}
+
+#[wasm_bindgen_test]
+async fn integer_results() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} transferIn(ep, len) {{
+            return Promise.resolve(new USBInTransferResult('ok', new DataView(new Uint8Array([1, 2, 3, 4].slice(0, len)).buffer)));
+        }} }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let res = open.transfer_in_detailed(1, 2).await.unwrap();
+    assert_eq!(res.as_u16_le().unwrap(), 0x0201);
+    assert_eq!(res.as_u16_be().unwrap(), 0x0102);
+    assert_eq!(res.as_u32_le().unwrap_err().kind(), ErrorKind::InvalidData);
+
+    let res = open.transfer_in_detailed(1, 4).await.unwrap();
+    assert_eq!(res.as_u32_le().unwrap(), 0x04030201);
+    assert_eq!(res.as_u32_be().unwrap(), 0x01020304);
+    assert_eq!(res.as_u16_le().unwrap_err().kind(), ErrorKind::InvalidData);
+    assert_eq!(res.as_u16_be().unwrap_err().kind(), ErrorKind::InvalidData);
+
+    let res = open.transfer_in_detailed(1, 3).await.unwrap();
+    let err = res.as_u32_be().unwrap_err();
+    assert_eq!(err.msg(), "USB device sent 3 bytes, but 4 bytes were expected");
+}
+
+#[wasm_bindgen_test]
+async fn metered_reader() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} transferIn(ep, len) {{
+            return new Promise(resolve => setTimeout(
+                () => resolve(new USBInTransferResult('ok', new DataView(new Uint8Array(len).buffer))), 2));
+        }} }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let (reader, throughput) = open.metered_reader(1, 64);
+    assert_eq!(throughput.bytes_per_sec(), 0.);
+
+    let mut reader = pin!(reader);
+    for _ in 0..5 {
+        assert_eq!(reader.next().await.unwrap().unwrap().len(), 64);
+    }
+    assert_eq!(throughput.bytes(), 320);
+    assert!(throughput.bytes_per_sec() > 0., "{}", throughput.bytes_per_sec());
+}
+
+#[wasm_bindgen_test]
+async fn multiplexer() {
+    mock::install_classes();
+    // Echoes each pair of requests in reverse order.
+    let dev = mock::device(&format!(
+        "{{ {} sent: [], replies: [], waiting: [],
+            flush() {{
+                while (this.waiting.length && this.replies.length) {{
+                    const data = this.replies.shift();
+                    this.waiting.shift()(new USBInTransferResult('ok', new DataView(data.buffer)));
+                }}
+            }},
+            transferOut(ep, data) {{
+                this.sent.push(new Uint8Array(data));
+                if (this.sent.length == 2) {{
+                    this.replies.push(this.sent.pop(), this.sent.pop());
+                    this.flush();
+                }}
+                return Promise.resolve(new USBOutTransferResult('ok', data.byteLength));
+            }},
+            transferIn(ep, len) {{
+                return new Promise(resolve => {{ this.waiting.push(resolve); this.flush(); }});
+            }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = Rc::new(dev.open().await.unwrap());
+    let mux = Multiplexer::new(open, 1, 2, 64, |data| data.first().map(|&id| u64::from(id)));
+
+    let (a, b) = futures_util::join!(mux.request(1, &[1, 10]), mux.request(2, &[2, 20]));
+    assert_eq!(a.unwrap(), [1, 10]);
+    assert_eq!(b.unwrap(), [2, 20]);
+}
+
+#[wasm_bindgen_test]
+async fn read_reassembled() {
+    mock::install_classes();
+    // Each packet starts with a flag indicating whether more packets follow.
+    let dev = mock::device(&format!(
+        "{{ {} packets: [[1, 10, 11], [1, 12, 13], [0, 14], [0, 99]],
+            transferIn(ep, len) {{
+                const data = new Uint8Array(this.packets.shift().slice(0, len));
+                return Promise.resolve(new USBInTransferResult('ok', new DataView(data.buffer)));
+            }},
+        }}",
+        mock::OPEN_CLOSE
+    ));
+    let open = dev.open().await.unwrap();
+
+    let message = open.read_reassembled(1, 3, |packet| packet[0] == 0).await.unwrap();
+    assert_eq!(message, [1, 10, 11, 1, 12, 13, 0, 14]);
+    let message = open.read_reassembled(1, 3, |packet| packet[0] == 0).await.unwrap();
+    assert_eq!(message, [0, 99]);
+}
+
+#[wasm_bindgen_test]
+async fn claim_check() {
+    mock::install_classes();
+    let dev = mock::device(&format!(
+        "{{ {} {}
+            transferIn(ep, len) {{ return Promise.resolve(new USBInTransferResult('ok', new DataView(new ArrayBuffer(len)))); }},
+            transferOut(ep, data) {{ return Promise.resolve(new USBOutTransferResult('ok', data.byteLength)); }},
+        }}",
+        mock::OPEN_CLOSE,
+        mock::CONFIGURED
+    ));
+    let open = dev.open().await.unwrap();
+
+    // Without the check the transfer is passed to the browser.
+    open.transfer_in(3, 8).await.unwrap();
+
+    open.set_claim_check(true);
+    let err = open.transfer_in(3, 8).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidAccess);
+    assert_eq!(err.msg(), "interface 1 not claimed");
+
+    open.claim_interface(0).await.unwrap();
+    open.transfer_in(1, 8).await.unwrap();
+    open.transfer_out(2, &[1, 2]).await.unwrap();
+
+    let err = open.transfer_out(5, &[1]).await.unwrap_err();
+    assert_eq!(err.msg(), "Out endpoint 5 does not belong to any interface");
+}
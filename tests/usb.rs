@@ -0,0 +1,450 @@
+use futures_util::{future, StreamExt};
+use std::{
+    cell::{Cell, RefCell},
+    pin::pin,
+    rc::Rc,
+    time::Duration,
+};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+mod mock;
+
+use webusb_web::*;
+
+#[wasm_bindgen_test]
+async fn polling() {
+    mock::install_usb();
+    let a = mock::add_device("{ productId: 1 }");
+    let b = mock::add_device("{ productId: 2 }");
+
+    let usb = Usb::new().unwrap();
+    let mut events = pin!(usb.with_polling(Duration::from_millis(10)));
+
+    // The first poll takes the baseline snapshot.
+    assert!(futures_util::poll!(events.next()).is_pending());
+    assert_eq!(usb.devices().await, [a.clone(), b]);
+
+    mock::remove_device(&a);
+    let c = mock::add_device("{ productId: 3 }");
+
+    match events.next().await.unwrap() {
+        UsbEvent::Disconnected(dev) => assert_eq!(dev, a),
+        other => panic!("unexpected event {other:?}"),
+    }
+    match events.next().await.unwrap() {
+        UsbEvent::Connected(dev) => assert_eq!(dev, c),
+        other => panic!("unexpected event {other:?}"),
+    }
+}
+
+#[wasm_bindgen_test]
+async fn disconnected() {
+    mock::install_usb();
+    let a = mock::add_device(&format!("{{ productId: 1, {} }}", mock::OPEN_CLOSE));
+    let b = mock::add_device("{ productId: 2 }");
+
+    let usb = Usb::new().unwrap();
+    let open = a.open().await.unwrap();
+    let mut disconnected = pin!(open.disconnected(&usb));
+
+    mock::dispatch("disconnect", &b);
+    assert!(futures_util::poll!(&mut disconnected).is_pending());
+
+    mock::dispatch("disconnect", &a);
+    disconnected.await;
+}
+
+#[wasm_bindgen_test]
+async fn request_and_open() {
+    mock::install_usb();
+    let usb = Usb::new().unwrap();
+    let filter = UsbDeviceFilter::new().with_vendor_id(1);
+
+    let err = usb.request_and_open(filter.clone()).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NoDeviceSelected);
+
+    let dev = mock::add_device(&format!("{{ vendorId: 1, {} }}", mock::OPEN_CLOSE));
+    let open = usb.request_and_open(filter).await.unwrap();
+    assert_eq!(open.device(), &dev);
+    assert!(dev.opened());
+}
+
+#[wasm_bindgen_test]
+async fn persistent_device() {
+    mock::install_usb();
+    mock::add_device("{ vendorId: 2 }");
+    let dev = mock::add_device(&format!("{{ vendorId: 1, {} }}", mock::OPEN_CLOSE));
+
+    let usb = Usb::new().unwrap();
+    let mut states = pin!(usb.persistent_device(UsbDeviceFilter::new().with_vendor_id(1)));
+
+    let Some(DeviceState::Connected(open)) = states.next().await else { panic!("device not opened") };
+    assert_eq!(open.device(), &dev);
+
+    mock::remove_device(&dev);
+    mock::dispatch("disconnect", &dev);
+    assert!(matches!(states.next().await, Some(DeviceState::Disconnected)));
+    drop(open);
+
+    let other = mock::add_device(&format!("{{ vendorId: 2, {} }}", mock::OPEN_CLOSE));
+    mock::dispatch("connect", &other);
+    let dev = mock::add_device(&format!("{{ vendorId: 1, {} }}", mock::OPEN_CLOSE));
+    mock::dispatch("connect", &dev);
+    let Some(DeviceState::Connected(open)) = states.next().await else { panic!("device not reopened") };
+    assert_eq!(open.device(), &dev);
+    assert!(!other.opened());
+}
+
+#[wasm_bindgen_test]
+async fn on_lag() {
+    mock::install_usb();
+    let usb = Usb::new().unwrap();
+
+    let lagged = Rc::new(Cell::new(0));
+    let handler_lagged = lagged.clone();
+    usb.on_lag(move || handler_lagged.set(handler_lagged.get() + 1));
+
+    let mut events = usb.events();
+    let dev = mock::add_device("{}");
+    for _ in 0..2000 {
+        mock::dispatch("connect", &dev);
+    }
+
+    assert!(matches!(events.next().await, Some(UsbEvent::Connected(_))));
+    assert_eq!(lagged.get(), 1);
+}
+
+#[wasm_bindgen_test]
+async fn on_lag_replace_from_handler() {
+    mock::install_usb();
+    let usb = Rc::new(Usb::new().unwrap());
+
+    let lagged = Rc::new(Cell::new(0));
+    let handler_usb = usb.clone();
+    let handler_lagged = lagged.clone();
+    usb.on_lag(move || {
+        let replaced_lagged = handler_lagged.clone();
+        handler_usb.on_lag(move || replaced_lagged.set(replaced_lagged.get() + 10));
+        handler_lagged.set(handler_lagged.get() + 1);
+    });
+
+    let mut events = usb.events();
+    let dev = mock::add_device("{}");
+    for _ in 0..2000 {
+        mock::dispatch("connect", &dev);
+    }
+    assert!(matches!(events.next().await, Some(UsbEvent::Connected(_))));
+    assert_eq!(lagged.get(), 1);
+
+    while futures_util::poll!(events.next()).is_ready() {}
+    for _ in 0..2000 {
+        mock::dispatch("connect", &dev);
+    }
+    assert!(matches!(events.next().await, Some(UsbEvent::Connected(_))));
+    assert_eq!(lagged.get(), 11);
+}
+
+#[wasm_bindgen_test]
+async fn request_device_cancellable() {
+    mock::install_usb();
+    let dev = mock::add_device("{ vendorId: 1 }");
+    let usb = Usb::new().unwrap();
+
+    let res = usb.request_device_cancellable([UsbDeviceFilter::new()], future::pending()).await;
+    assert_eq!(res.unwrap(), dev);
+
+    js_sys::eval("navigator.usb.requestDevice = () => new Promise(() => {})").unwrap();
+    let err = usb.request_device_cancellable([UsbDeviceFilter::new()], future::ready(())).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Cancelled);
+}
+
+#[wasm_bindgen_test]
+async fn request_device_without_gesture() {
+    mock::install_usb();
+    js_sys::eval(
+        "navigator.usb.requestDevice = () => Promise.reject(
+            new DOMException('Must be handling a user gesture to show a permission request.', 'SecurityError'))",
+    )
+    .unwrap();
+    let usb = Usb::new().unwrap();
+
+    let err = usb.request_device([UsbDeviceFilter::new()]).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Security);
+    assert!(err.msg().starts_with("requestDevice must be called from a user gesture"), "{}", err.msg());
+}
+
+#[wasm_bindgen_test]
+async fn request_device_blocked_by_permissions_policy() {
+    mock::install_usb();
+    js_sys::eval(
+        "navigator.usb.requestDevice = () => Promise.reject(
+            new DOMException('Access to the feature \"usb\" is disallowed by permissions policy.', 'SecurityError'))",
+    )
+    .unwrap();
+    let usb = Usb::new().unwrap();
+
+    let err = usb.request_device([UsbDeviceFilter::new()]).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Security);
+    assert!(!err.msg().contains("must be called from a user gesture"), "{}", err.msg());
+    assert!(err.msg().contains("missing user gesture or a permissions policy"), "{}", err.msg());
+    assert!(err.msg().contains("disallowed by permissions policy"), "{}", err.msg());
+}
+
+#[wasm_bindgen_test]
+async fn managed_device() {
+    mock::install_classes();
+    mock::install_usb();
+    let dev = mock::add_device(&format!("{{ vendorId: 1, {} {} }}", mock::OPEN_CLOSE, mock::CONFIGURED));
+
+    let usb = Usb::new().unwrap();
+    let managed = ManagedDevice::new(&usb, UsbDeviceFilter::new().with_vendor_id(1));
+    let mut connection = pin!(managed.connection());
+
+    let open = connection.next().await.unwrap().unwrap();
+    assert_eq!(open.device(), &dev);
+    assert_eq!(open.claimed_interfaces(), [1]);
+
+    mock::remove_device(&dev);
+    mock::dispatch("disconnect", &dev);
+    assert!(connection.next().await.unwrap().is_none());
+    drop(open);
+    assert!(futures_util::poll!(connection.next()).is_pending());
+
+    let dev = mock::add_device(&format!("{{ vendorId: 1, {} {} }}", mock::OPEN_CLOSE, mock::CONFIGURED));
+    mock::dispatch("connect", &dev);
+    let open = connection.next().await.unwrap().unwrap();
+    assert_eq!(open.device(), &dev);
+    assert!(dev.opened());
+}
+
+#[wasm_bindgen_test]
+async fn managed_device_claim_failure() {
+    mock::install_classes();
+    mock::install_usb();
+    let dev = mock::add_device(&format!(
+        "{{ vendorId: 1, {} {}
+            claimInterface(iface) {{ return Promise.reject(new DOMException('Busy.', 'NetworkError')); }} }}",
+        mock::OPEN_CLOSE,
+        mock::CONFIGURED
+    ));
+
+    let usb = Usb::new().unwrap();
+    let managed = ManagedDevice::new(&usb, UsbDeviceFilter::new().with_vendor_id(1));
+    let mut connection = pin!(managed.connection());
+    assert!(futures_util::poll!(connection.next()).is_pending());
+    mock::idle().await;
+
+    mock::remove_device(&dev);
+    mock::dispatch("disconnect", &dev);
+    let dev = mock::add_device(&format!("{{ vendorId: 1, {} {} }}", mock::OPEN_CLOSE, mock::CONFIGURED));
+    mock::dispatch("connect", &dev);
+
+    let open = connection.next().await.unwrap().unwrap();
+    assert_eq!(open.device(), &dev);
+    assert_eq!(open.claimed_interfaces(), [1]);
+}
+
+#[wasm_bindgen_test]
+async fn new_in() {
+    mock::install_usb();
+    let global = js_sys::eval(
+        "({ navigator: { usb: Object.assign(new EventTarget(), {
+            getDevices() { return Promise.resolve([new USBDevice({ productId: 7 })]); },
+        }) } })",
+    )
+    .unwrap();
+    let usb = Usb::new_in(&global).unwrap();
+    assert_eq!(usb.context(), UsbContext::Unknown);
+    let devices = usb.devices().await;
+    assert_eq!(devices.len(), 1);
+    assert_eq!(devices[0].product_id(), 7);
+
+    let err = Usb::new_in(&js_sys::eval("({ navigator: {} })").unwrap()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+    let err = Usb::new_in(&js_sys::Object::new()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+}
+
+#[wasm_bindgen_test]
+async fn events_buffered() {
+    mock::install_usb();
+    let usb = Usb::new().unwrap();
+
+    let lagged = Rc::new(Cell::new(0));
+    let handler_lagged = lagged.clone();
+    usb.on_lag(move || handler_lagged.set(handler_lagged.get() + 1));
+
+    let mut events = usb.events();
+    let mut buffered = pin!(usb.events_buffered(2000));
+    let dev = mock::add_device("{}");
+    for _ in 0..30 {
+        for _ in 0..100 {
+            mock::dispatch("connect", &dev);
+        }
+        mock::idle().await;
+    }
+
+    assert!(matches!(events.next().await, Some(UsbEvent::Connected(_))));
+    assert_eq!(lagged.get(), 1, "unbuffered stream must lag");
+
+    for _ in 0..3000 {
+        assert!(matches!(buffered.next().await, Some(UsbEvent::Connected(_))));
+    }
+    assert_eq!(lagged.get(), 1, "buffered stream must not lag");
+}
+
+#[wasm_bindgen_test]
+async fn open_first_matching() {
+    mock::install_usb();
+    let usb = Usb::new().unwrap();
+    let filter = UsbDeviceFilter::new().with_vendor_id(1);
+
+    mock::add_device(&format!("{{ vendorId: 2, {} }}", mock::OPEN_CLOSE));
+    assert!(usb.open_first_matching(filter.clone()).await.unwrap().is_none());
+
+    let dev = mock::add_device(&format!("{{ vendorId: 1, {} }}", mock::OPEN_CLOSE));
+    let open = usb.open_first_matching(filter).await.unwrap().unwrap();
+    assert_eq!(open.device(), &dev);
+    assert!(dev.opened());
+}
+
+#[wasm_bindgen_test]
+async fn auto_connect() {
+    mock::install_usb();
+    let usb = Usb::new().unwrap();
+    let a = mock::add_device(&format!("{{ vendorId: 1, productId: 1, {} }}", mock::OPEN_CLOSE));
+    mock::add_device(&format!("{{ vendorId: 2, {} }}", mock::OPEN_CLOSE));
+
+    let opened = Rc::new(RefCell::new(Vec::new()));
+    let handler_opened = opened.clone();
+    let watch = usb.auto_connect(UsbDeviceFilter::new().with_vendor_id(1), move |open| {
+        handler_opened.borrow_mut().push(open)
+    });
+    let product_ids = || opened.borrow().iter().map(|open| open.device().product_id()).collect::<Vec<_>>();
+    mock::idle().await;
+    assert_eq!(product_ids(), [1]);
+
+    let failing = mock::add_device(
+        "{ vendorId: 1, productId: 2, opened: false,
+           open() { return Promise.reject(new DOMException('busy', 'NetworkError')); } }",
+    );
+    mock::dispatch("connect", &failing);
+    let b = mock::add_device(&format!("{{ vendorId: 1, productId: 3, {} }}", mock::OPEN_CLOSE));
+    mock::dispatch("connect", &b);
+    mock::dispatch("connect", &a);
+    mock::idle().await;
+    assert_eq!(product_ids(), [1, 3]);
+
+    watch.stop();
+    let c = mock::add_device(&format!("{{ vendorId: 1, productId: 4, {} }}", mock::OPEN_CLOSE));
+    mock::dispatch("connect", &c);
+    mock::idle().await;
+    assert_eq!(product_ids(), [1, 3]);
+}
+
+#[wasm_bindgen_test]
+async fn devices_by_vendor() {
+    mock::install_usb();
+    let a = mock::add_device("{ vendorId: 2, productId: 1 }");
+    let b = mock::add_device("{ vendorId: 1, productId: 2 }");
+    let c = mock::add_device("{ vendorId: 2, productId: 3 }");
+    let usb = Usb::new().unwrap();
+
+    let groups = usb.devices_by_vendor().await;
+    assert_eq!(groups.keys().copied().collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(groups[&1], [b]);
+    assert_eq!(groups[&2], [a, c]);
+}
+
+#[wasm_bindgen_test]
+async fn request_device_retry() {
+    mock::install_usb();
+    let usb = Usb::new().unwrap();
+    let gestures = Cell::new(0);
+    let gesture = || {
+        gestures.set(gestures.get() + 1);
+        future::ready(())
+    };
+
+    let err = usb.request_device_retry([UsbDeviceFilter::new()], 3, gesture).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NoDeviceSelected);
+    assert_eq!(gestures.get(), 3);
+
+    // The user selects a device on the second attempt.
+    let dev = mock::add_device("{ vendorId: 1 }");
+    js_sys::eval(
+        "const request = navigator.usb.requestDevice; let calls = 0;
+         navigator.usb.requestDevice = function(options) {
+             return calls++ ? request.call(this, options)
+                            : Promise.reject(new DOMException('No device selected.', 'NotFoundError'));
+         }",
+    )
+    .unwrap();
+    gestures.set(0);
+    let res = usb.request_device_retry([UsbDeviceFilter::new()], 3, gesture).await;
+    assert_eq!(res.unwrap(), dev);
+    assert_eq!(gestures.get(), 2);
+}
+
+#[wasm_bindgen_test]
+async fn devices_ready() {
+    mock::install_usb();
+    let usb = Usb::new().unwrap();
+
+    assert!(usb.devices_ready(Duration::from_millis(30)).await.is_empty());
+
+    let ready = usb.devices_ready(Duration::from_secs(5));
+    let add = async {
+        mock::idle().await;
+        mock::add_device("{ vendorId: 1 }")
+    };
+    let (devices, dev) = futures_util::join!(ready, add);
+    assert_eq!(devices, [dev]);
+}
+
+#[wasm_bindgen_test]
+async fn close_all() {
+    mock::install_usb();
+    let usb = Usb::new().unwrap();
+    let a = mock::add_device(&format!("{{ productId: 1, {} }}", mock::OPEN_CLOSE));
+    let b = mock::add_device(&format!("{{ productId: 2, {} }}", mock::OPEN_CLOSE));
+    let c = mock::add_device(&format!("{{ productId: 3, {} }}", mock::OPEN_CLOSE));
+
+    let open_a = a.open().await.unwrap();
+    let open_b = b.open().await.unwrap();
+    let open_c = c.open().await.unwrap();
+    usb.track(&open_a);
+    usb.track(&open_b);
+
+    usb.close_all().await.unwrap();
+    assert!(!a.opened());
+    assert!(!b.opened());
+    assert!(c.opened(), "untracked device must stay open");
+
+    // Closed devices can be opened again while the old handles still exist.
+    assert!(!a.opened_handle());
+    let reopened = a.open().await.unwrap();
+    drop((open_a, open_b, open_c));
+    mock::idle().await;
+    assert!(a.opened(), "dropping a closed handle must not close the device again");
+    assert!(a.opened_handle());
+    drop(reopened);
+}
+
+#[wasm_bindgen_test]
+async fn try_devices() {
+    mock::install_usb();
+    let dev = mock::add_device("{ productId: 1 }");
+    let usb = Usb::new().unwrap();
+    assert_eq!(usb.try_devices().await.unwrap(), [dev]);
+
+    js_sys::eval("navigator.usb.getDevices = () => Promise.resolve([{ productId: 2 }])").unwrap();
+    assert_eq!(usb.try_devices().await.unwrap_err().kind(), ErrorKind::Other);
+
+    js_sys::eval(
+        "navigator.usb.getDevices = () => Promise.reject(new DOMException('Access denied.', 'SecurityError'))",
+    )
+    .unwrap();
+    assert_eq!(usb.try_devices().await.unwrap_err().kind(), ErrorKind::Security);
+    assert!(usb.devices().await.is_empty());
+}